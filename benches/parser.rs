@@ -0,0 +1,50 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lockjaw::lexer::{Lexeme, Lexer, LexingError};
+use lockjaw::parser::{Parser, DEFAULT_MAX_DEPTH};
+
+fn nested(depth: usize) -> String {
+	format!("{}x{}", "{".repeat(depth), "}".repeat(depth))
+}
+
+// Many sibling top level forms, like a generated data file.
+fn records(count: usize) -> String {
+	(0..count)
+		.map(|i| format!("(def {{record-{i}}} {{:id {i} :name \"name {i}\" :score {i}.5}})\n"))
+		.collect()
+}
+
+// One long list, every element a nested triple.
+fn wide(count: usize) -> String {
+	let items: String = (0..count)
+		.map(|i| format!("{{{i} {{{i} {{{i}}}}}}} "))
+		.collect();
+	format!("{{{items}}}")
+}
+
+fn lex(text: &str) -> Vec<Lexeme<'_>> {
+	Lexer::new(text)
+		.collect::<Result<_, LexingError>>()
+		.unwrap()
+}
+
+fn parse(c: &mut Criterion) {
+	let mut group = c.benchmark_group("parse");
+	for (name, text) in [
+		("nested", nested(DEFAULT_MAX_DEPTH)),
+		("records", records(10_000)),
+		("wide", wide(50_000)),
+	] {
+		let lexemes = lex(&text);
+		group.throughput(Throughput::Elements(lexemes.len() as u64));
+		group.bench_with_input(BenchmarkId::new("lexemes", name), &lexemes, |b, lexemes| {
+			b.iter(|| Parser::parse_root(lexemes).unwrap())
+		});
+		group.bench_with_input(BenchmarkId::new("text", name), &text, |b, text| {
+			b.iter(|| Parser::parse_from_text(text).unwrap())
+		});
+	}
+	group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lockjaw::evaluator::Evaluator;
+use lockjaw::parser::Parser;
+
+fuzz_target!(|text: &str| {
+	// `load`, `require` and `import` would let the fuzzer read arbitrary files,
+	// including ones that never end.
+	if ["load", "require", "import"]
+		.iter()
+		.any(|name| text.contains(name))
+	{
+		return;
+	}
+	if let Ok(expression) = Parser::parse_from_text(text) {
+		let _ = Evaluator::new().evaluate(expression);
+	}
+});
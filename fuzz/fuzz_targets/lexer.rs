@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lockjaw::lexer::Lexer;
+
+fuzz_target!(|text: &str| {
+	for lexeme in Lexer::new(text) {
+		if lexeme.is_err() {
+			break;
+		}
+	}
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lockjaw::parser::Parser;
+use lockjaw::types::Expression;
+
+fuzz_target!(|text: &str| {
+	// Anything that parses must print to something that parses back the same.
+	if let Ok(Expression::SExpression(expressions)) = Parser::parse_from_text(text) {
+		let written: Vec<String> = expressions.iter().map(|e| e.write().to_string()).collect();
+		let written = written.join(" ");
+		match Parser::parse_from_text(&written) {
+			Ok(reparsed) => assert_eq!(Expression::SExpression(expressions), reparsed),
+			Err(e) => panic!("{written:?} did not parse back: {e}"),
+		}
+	}
+});
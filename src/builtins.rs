@@ -1,7 +1,58 @@
+use crate::lexer::Lexer;
 use crate::numeric::Numeric;
 use crate::types::*;
 use std::collections::VecDeque;
 
+/// How a call to the builtin registered as `name` looks, for help and REPL hints.
+pub fn signature(name: &str) -> Option<&'static str> {
+	Some(match name {
+		"+" => "(+ number...)",
+		"-" => "(- number...)",
+		"*" => "(* number...)",
+		"/" => "(/ number...)",
+		"car" => "(car {list})",
+		"cdr" => "(cdr {list})",
+		"join" => "(join {list} {list})",
+		"list" => "(list value...)",
+		"eval" => "(eval {expression})",
+		"def" => "(def {name...} value...)",
+		"cond" => "(cond {test body}...)",
+		"load" => "(load path)",
+		"require" => "(require path)",
+		"module" => "(module {exports...})",
+		"import" => "(import path :as prefix :only {names...})",
+		"set-reader-macro" => "(set-reader-macro name handler)",
+		"set-reader-prefix" => "(set-reader-prefix character handler)",
+		"print" => "(print value...)",
+		"write" => "(write value...)",
+		"display" => "(display value...)",
+		"format" => "(format template argument...)",
+		"fun" => "(fun {argument...} {body})",
+		"null?" => "(null? value)",
+		"atom?" => "(atom? value)",
+		"and?" => "(and? bool bool)",
+		"or?" => "(or? bool bool)",
+		"xor?" => "(xor? bool bool)",
+		"gt?" => "(gt? number number)",
+		"lt?" => "(lt? number number)",
+		"eq?" => "(eq? value value)",
+		"zero?" => "(zero? number)",
+		"integer?" => "(integer? value)",
+		"keyword?" => "(keyword? value)",
+		"keyword->string" => "(keyword->string keyword)",
+		"string->keyword" => "(string->keyword string)",
+		"keyword->symbol" => "(keyword->symbol keyword)",
+		"symbol->keyword" => "(symbol->keyword symbol)",
+		"exit" => "(exit status)",
+		"error" => "(error message)",
+		"deftest" => "(deftest name {form...})",
+		"assert-eq" => "(assert-eq expected actual)",
+		"assert-true" => "(assert-true value)",
+		"assert-error" => "(assert-error {expression})",
+		_ => return None,
+	})
+}
+
 pub fn add(args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
 	if args.is_empty() {
 		return Err(LockjawRuntimeError::InvalidArgumentCount(
@@ -12,7 +63,10 @@ pub fn add(args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError
 	for expr in args {
 		let value = expr.get_atom()?;
 		accumulator = match (value, accumulator) {
-			(Atom::Number(a), Atom::Number(b)) => Atom::Number(a + b),
+			(Atom::Number(a), Atom::Number(b)) => Atom::Number(
+				a.checked_add(b)
+					.ok_or_else(|| LockjawRuntimeError::IntegerOverflow(String::from("+")))?,
+			),
 			_ => {
 				return Err(LockjawRuntimeError::InvalidArguments(
 					"Cannot add non-number".to_string(),
@@ -34,7 +88,10 @@ pub fn sub(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeE
 
 	if args.is_empty() {
 		return Ok(Expression::Atom(match accumulator {
-			Atom::Number(num) => Atom::Number(-num),
+			Atom::Number(num) => Atom::Number(
+				num.checked_neg()
+					.ok_or_else(|| LockjawRuntimeError::IntegerOverflow(String::from("-")))?,
+			),
 			_ => {
 				return Err(LockjawRuntimeError::InvalidArguments(
 					"Cannot negate non-number".to_string(),
@@ -46,7 +103,10 @@ pub fn sub(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeE
 	for expr in args {
 		let value = expr.get_atom()?;
 		accumulator = match (accumulator, value) {
-			(Atom::Number(a), Atom::Number(b)) => Atom::Number(a - b),
+			(Atom::Number(a), Atom::Number(b)) => Atom::Number(
+				a.checked_sub(b)
+					.ok_or_else(|| LockjawRuntimeError::IntegerOverflow(String::from("-")))?,
+			),
 			_ => {
 				return Err(LockjawRuntimeError::InvalidArguments(
 					"Cannot Subtract non-number".to_string(),
@@ -67,7 +127,10 @@ pub fn mul(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeE
 	for expr in args {
 		let value = expr.get_atom()?;
 		accumulator = match (value, accumulator) {
-			(Atom::Number(a), Atom::Number(b)) => Atom::Number(a * b),
+			(Atom::Number(a), Atom::Number(b)) => Atom::Number(
+				a.checked_mul(b)
+					.ok_or_else(|| LockjawRuntimeError::IntegerOverflow(String::from("*")))?,
+			),
 			_ => {
 				return Err(LockjawRuntimeError::InvalidArguments(
 					"Cannot multiply a non-number".to_string(),
@@ -146,6 +209,10 @@ pub fn join(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntime
 	Ok(Expression::QExpression(a))
 }
 
+pub fn list(args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	Ok(Expression::QExpression(args))
+}
+
 pub fn fun(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
 	if args.len() != 2 {
 		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
@@ -160,6 +227,7 @@ pub fn fun(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeE
 		args: formals,
 		body,
 		curried: VecDeque::new(),
+		module: None,
 	}))))
 }
 
@@ -301,8 +369,9 @@ pub fn eq_q(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntime
 
 	match (a, b) {
 		(Atom::Number(a), Atom::Number(b)) => Ok(Expression::Atom(Atom::Bool(a == b))),
+		(Atom::Keyword(a), Atom::Keyword(b)) => Ok(Expression::Atom(Atom::Bool(a == b))),
 		_ => Err(LockjawRuntimeError::InvalidArguments(String::from(
-			"Arguments to eq? must be numeric.",
+			"Arguments to eq? must both be numeric or both be keywords.",
 		))),
 	}
 }
@@ -322,6 +391,90 @@ pub fn zero_q(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRunti
 	}
 }
 
+pub fn integer_q(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"integer? takes exactly one argument",
+		)));
+	}
+
+	match args.pop_front().unwrap() {
+		Expression::Atom(Atom::Number(Numeric::Int(_))) => Ok(Expression::Atom(Atom::Bool(true))),
+		_ => Ok(Expression::Atom(Atom::Bool(false))),
+	}
+}
+
+pub fn keyword_q(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"keyword? takes exactly one argument",
+		)));
+	}
+
+	match args.pop_front().unwrap() {
+		Expression::Atom(Atom::Keyword(_)) => Ok(Expression::Atom(Atom::Bool(true))),
+		_ => Ok(Expression::Atom(Atom::Bool(false))),
+	}
+}
+
+pub fn keyword_to_string(
+	mut args: VecDeque<Expression>,
+) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"keyword->string takes exactly one argument",
+		)));
+	}
+
+	let keyword = args.pop_front().unwrap().get_atom()?.get_as_keyword()?;
+	Ok(Expression::Atom(Atom::String(keyword.into())))
+}
+
+pub fn string_to_keyword(
+	mut args: VecDeque<Expression>,
+) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"string->keyword takes exactly one argument",
+		)));
+	}
+
+	// Only names the lexer reads back after a `:` make keywords.
+	let name = args.pop_front().unwrap().get_atom()?.get_as_string()?;
+	if name.is_empty() || !name.chars().all(Lexer::is_symbol_continue) {
+		return Err(LockjawRuntimeError::InvalidArguments(format!(
+			"{name:?} is not a valid keyword name"
+		)));
+	}
+	Ok(Expression::Atom(Atom::Keyword(Keyword::new(&name))))
+}
+
+pub fn keyword_to_symbol(
+	mut args: VecDeque<Expression>,
+) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"keyword->symbol takes exactly one argument",
+		)));
+	}
+
+	let keyword = args.pop_front().unwrap().get_atom()?.get_as_keyword()?;
+	Ok(Expression::Atom(Atom::Symbol(keyword.into())))
+}
+
+pub fn symbol_to_keyword(
+	mut args: VecDeque<Expression>,
+) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"symbol->keyword takes exactly one argument",
+		)));
+	}
+
+	let symbol = args.pop_front().unwrap().get_atom()?.get_as_symbol()?;
+	Ok(Expression::Atom(Atom::Keyword(Keyword::new(&symbol))))
+}
+
 #[allow(unused_mut)]
 pub fn print(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
 	println!("{:?}", args);
@@ -329,3 +482,99 @@ pub fn print(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntim
 }
 
 // zero?
+
+pub fn format(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	if args.is_empty() {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"format requires a template string",
+		)));
+	}
+
+	let template = args.pop_front().unwrap().get_atom()?.get_as_string()?;
+	Ok(Expression::Atom(Atom::String(crate::format::format(
+		&template, args,
+	)?)))
+}
+
+pub fn exit(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"exit takes exactly one argument",
+		)));
+	}
+
+	let code = match args.pop_front().unwrap().get_atom()? {
+		Atom::Number(Numeric::Int(code)) => i32::try_from(code).map_err(|_| {
+			LockjawRuntimeError::InvalidArguments(format!("exit status {code} is out of range"))
+		})?,
+		invalid => {
+			return Err(LockjawRuntimeError::InvalidArguments(format!(
+				"Expected Integer exit status, got {invalid}"
+			)))
+		}
+	};
+	Err(LockjawRuntimeError::Exit(code))
+}
+
+pub fn error(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"error takes exactly one argument",
+		)));
+	}
+
+	match args.pop_front().unwrap() {
+		Expression::Atom(Atom::String(message)) => Err(LockjawRuntimeError::Raised(message)),
+		invalid => Err(LockjawRuntimeError::InvalidArguments(format!(
+			"Expected String error message, got {invalid}"
+		))),
+	}
+}
+
+pub fn assert_eq(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 2 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"assert-eq takes exactly two arguments",
+		)));
+	}
+
+	let expected = args.pop_front().unwrap();
+	let actual = args.pop_front().unwrap();
+	if expected == actual {
+		Ok(Expression::Null)
+	} else {
+		Err(LockjawRuntimeError::AssertionFailed(format!(
+			"values differ\n  - expected: {}\n  + actual:   {}",
+			expected.write(),
+			actual.write()
+		)))
+	}
+}
+
+pub fn assert_true(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	if args.len() != 1 {
+		return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+			"assert-true takes exactly one argument",
+		)));
+	}
+
+	match args.pop_front().unwrap() {
+		Expression::Atom(Atom::Bool(true)) => Ok(Expression::Null),
+		value => Err(LockjawRuntimeError::AssertionFailed(format!(
+			"expected #t, got {}",
+			value.write()
+		))),
+	}
+}
+
+pub fn write(args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	let written: Vec<String> = args.iter().map(|e| e.write().to_string()).collect();
+	println!("{}", written.join(" "));
+	Ok(Expression::Null)
+}
+
+pub fn display(args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+	let displayed: Vec<String> = args.iter().map(|e| e.display().to_string()).collect();
+	println!("{}", displayed.join(" "));
+	Ok(Expression::Null)
+}
@@ -1,10 +1,17 @@
 use crate::types::Value;
 use std::collections::HashMap;
 
+#[derive(Clone)]
 pub struct Environment {
 	internal: Vec<HashMap<String, Value>>,
 }
 
+impl Default for Environment {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl Environment {
 	pub fn new() -> Environment {
 		Environment {
@@ -30,6 +37,25 @@ impl Environment {
 			.or_else(|| self.internal[0].get(k.as_str()))
 	}
 
+	/// Definitions in the global frame.
+	pub fn globals(&self) -> &HashMap<String, Value> {
+		&self.internal[0]
+	}
+
+	/// Exchanges the global frame with `globals`, local frames are untouched.
+	pub fn swap_globals(&mut self, globals: &mut HashMap<String, Value>) {
+		std::mem::swap(&mut self.internal[0], globals);
+	}
+
+	/// Every name `get` could currently resolve, in no particular order.
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		let local = self.internal.last().filter(|_| self.internal.len() > 1);
+		self.internal[0]
+			.keys()
+			.chain(local.into_iter().flat_map(|frame| frame.keys()))
+			.map(String::as_str)
+	}
+
 	pub fn push_env(&mut self) {
 		self.internal.push(HashMap::new())
 	}
@@ -1,14 +1,62 @@
 use crate::builtins;
 use crate::environment::Environment;
-use crate::parser;
+use crate::lexer::{Lexeme, Lexer, LexingError};
+use crate::parser::{Parser, ParsingError, FORMAT_HEAD};
+use crate::prelude::PRELUDE;
+use crate::reader::Reader;
+use crate::readtable::{MacroName, ReadTable, ReaderMacros};
 use crate::types::*;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
+/// Deepest chain of user function calls before evaluation gives up, so runaway
+/// recursion is reported as an error instead of exhausting memory.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+// Evaluation recurses on the native stack, which is grown in segments of
+// `STACK_SEGMENT` whenever less than `STACK_RED_ZONE` remains.
+const STACK_RED_ZONE: usize = 128 * 1024;
+const STACK_SEGMENT: usize = 2 * 1024 * 1024;
+
+// A file evaluated by `import`, with globals of its own.
+#[derive(Clone)]
+struct Module {
+	// The module's globals while another module is being evaluated.
+	globals: HashMap<String, Value>,
+	// Names declared with `module`, `None` until the file declares them.
+	exports: Option<Vec<String>>,
+	// Canonical paths `require` has finished loading into this module.
+	required: HashSet<PathBuf>,
+}
+
+#[derive(Clone)]
 pub struct Evaluator {
 	env: Environment,
+	read_table: ReadTable,
+	call_depth: usize,
+	steps: u64,
+	// Canonical paths of the files being loaded, innermost last.
+	loading: Vec<PathBuf>,
+	// Directories searched for files not found next to the loading file.
+	search_path: Vec<PathBuf>,
+	// The program itself is module 0, `current_module` is the one whose
+	// globals are in `env`.
+	modules: Vec<Module>,
+	current_module: usize,
+	// Canonical paths of imported files to their index in `modules`.
+	module_paths: HashMap<PathBuf, usize>,
+	// Globals every module starts with.
+	builtins: HashMap<String, Value>,
+	// Names and bodies registered with `deftest`, in the order they were defined.
+	tests: Vec<(String, VecDeque<Expression>)>,
+}
+
+impl Default for Evaluator {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Evaluator {
@@ -55,40 +103,448 @@ impl Evaluator {
 		Err(LockjawRuntimeError::CondFailure)
 	}
 
-	pub fn load(
+	// The file name passed to `load` or `require`, `None` when it is not a string.
+	fn path_argument(
+		name: &str,
+		mut args: VecDeque<Expression>,
+	) -> Result<Option<String>, LockjawRuntimeError> {
+		if args.len() != 1 {
+			return Err(LockjawRuntimeError::InvalidArgumentCount(format!(
+				"{name} takes a single file name as input."
+			)));
+		}
+
+		if let Expression::Atom(Atom::String(path)) = args.pop_front().unwrap() {
+			Ok(Some(path))
+		} else {
+			Ok(None)
+		}
+	}
+
+	pub fn load(&mut self, args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+		match Self::path_argument("load", args)? {
+			Some(path) => self.load_file(Path::new(path.as_str())),
+			None => Ok(Expression::Null),
+		}
+	}
+
+	pub fn require(
+		&mut self,
+		args: VecDeque<Expression>,
+	) -> Result<Expression, LockjawRuntimeError> {
+		match Self::path_argument("require", args)? {
+			Some(path) => self.require_file(Path::new(path.as_str())),
+			None => Ok(Expression::Null),
+		}
+	}
+
+	/// Directories `load`, `require` and `import` search, in order, for
+	/// relative paths that are not found next to the file being loaded.
+	pub fn set_search_path(&mut self, directories: Vec<PathBuf>) {
+		self.search_path = directories;
+	}
+
+	pub fn search_path(&self) -> &[PathBuf] {
+		&self.search_path
+	}
+
+	// The canonical path of the file `path` names. Relative paths are taken
+	// from the directory of the file being loaded, or the working directory
+	// outside of any file, and then from the search path unless they start
+	// with `./` or `../`. Names without an extension may leave off `.lisp`.
+	fn find(&self, path: &Path) -> Result<PathBuf, LockjawRuntimeError> {
+		let here = match self.loading.last().and_then(|file| file.parent()) {
+			Some(directory) => directory.to_path_buf(),
+			None => PathBuf::from("."),
+		};
+		let mut searched = vec![here];
+		if path.is_relative() && !path.starts_with(".") && !path.starts_with("..") {
+			searched.extend(self.search_path.iter().cloned());
+		}
+		for directory in &searched {
+			let file = directory.join(path);
+			if file.is_file() {
+				return Ok(file.canonicalize()?);
+			}
+			if file.extension().is_none() && file.with_extension("lisp").is_file() {
+				return Ok(file.with_extension("lisp").canonicalize()?);
+			}
+		}
+		Err(LockjawRuntimeError::FileNotFound {
+			name: path.display().to_string(),
+			searched: if path.is_absolute() {
+				Vec::new()
+			} else {
+				searched
+			},
+		})
+	}
+
+	/// Evaluates the program in the file at `path`, as `load` does.
+	pub fn load_file(&mut self, path: &Path) -> Result<Expression, LockjawRuntimeError> {
+		let path = self.find(path)?;
+		self.load_canonical(path)
+	}
+
+	fn load_canonical(&mut self, path: PathBuf) -> Result<Expression, LockjawRuntimeError> {
+		if let Some(start) = self.loading.iter().position(|file| *file == path) {
+			let mut chain = self.loading[start..].to_vec();
+			chain.push(path);
+			return Err(LockjawRuntimeError::LoadCycle(chain));
+		}
+		let input = BufReader::new(File::open(&path)?);
+		self.loading.push(path);
+		let result = self.load_reader(input);
+		self.loading.pop();
+		result
+	}
+
+	/// Evaluates the file at `path` unless `require` already has, as `require` does.
+	/// Each module keeps its own record, the definitions land in its globals.
+	pub fn require_file(&mut self, path: &Path) -> Result<Expression, LockjawRuntimeError> {
+		let canonical = self.find(path)?;
+		let module = self.current_module;
+		if self.modules[module].required.contains(&canonical) {
+			return Ok(Expression::Null);
+		}
+		self.load_file(&canonical)?;
+		self.modules[module].required.insert(canonical);
+		Ok(Expression::Null)
+	}
+
+	fn module(
 		&mut self,
 		mut args: VecDeque<Expression>,
 	) -> Result<Expression, LockjawRuntimeError> {
 		if args.len() != 1 {
 			return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
-				"load takes a single file name as input.",
+				"module takes a single list of exported names.",
 			)));
 		}
+		let exports = args
+			.pop_front()
+			.unwrap()
+			.get_from_q_expression()?
+			.into_iter()
+			.map(|name| name.get_atom()?.get_as_symbol())
+			.collect::<Result<_, _>>()?;
+		self.modules[self.current_module].exports = Some(exports);
+		Ok(Expression::Null)
+	}
 
-		if let Expression::Atom(Atom::String(path)) = args.pop_front().unwrap() {
-			let path = Path::new(path.as_str());
-			let mut f = File::open(path)?;
-			let mut s = String::new();
-
-			f.read_to_string(&mut s)?;
-			let expression = parser::Parser::parse_from_text(s.as_str())?;
-			match expression {
-				Expression::SExpression(statements) => {
-					for e in statements {
-						self.evaluate(e)?;
-					}
-					Ok(Expression::Null)
+	fn import(
+		&mut self,
+		mut args: VecDeque<Expression>,
+	) -> Result<Expression, LockjawRuntimeError> {
+		let path = match args.pop_front() {
+			Some(Expression::Atom(Atom::String(path))) => PathBuf::from(path),
+			_ => {
+				return Err(LockjawRuntimeError::InvalidArguments(String::from(
+					"import takes a file name followed by :as and :only options.",
+				)))
+			}
+		};
+		let mut prefix = None;
+		let mut only = None;
+		while let Some(option) = args.pop_front() {
+			match (option, args.pop_front()) {
+				(Expression::Atom(Atom::Keyword(k)), Some(Expression::Atom(Atom::String(p))))
+					if k.name() == "as" =>
+				{
+					prefix = Some(p)
+				}
+				(Expression::Atom(Atom::Keyword(k)), Some(Expression::QExpression(names)))
+					if k.name() == "only" =>
+				{
+					only = Some(
+						names
+							.into_iter()
+							.map(|name| name.get_atom()?.get_as_symbol())
+							.collect::<Result<Vec<_>, _>>()?,
+					)
+				}
+				(option, _) => {
+					return Err(LockjawRuntimeError::InvalidArguments(format!(
+						"unknown import option {option}, expected :as \"prefix\" or :only {{names...}}"
+					)))
+				}
+			}
+		}
+
+		// Names are qualified by the file name, unless they were picked out with :only.
+		let prefix = match prefix {
+			Some(prefix) => prefix,
+			None if only.is_some() => String::new(),
+			None => path
+				.file_stem()
+				.map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+		};
+		let id = self.import_module(&path)?;
+		let exports = self.modules[id].exports.clone().unwrap_or_default();
+		let names = match only {
+			Some(names) => {
+				if let Some(missing) = names.iter().find(|name| !exports.contains(name)) {
+					return Err(LockjawRuntimeError::InvalidArguments(format!(
+						"{} does not export {missing}",
+						path.display()
+					)));
+				}
+				names
+			}
+			None => exports,
+		};
+		for name in names {
+			let value = self.modules[id]
+				.globals
+				.get(&name)
+				.cloned()
+				.ok_or_else(|| {
+					LockjawRuntimeError::InvalidArguments(format!(
+						"{} exports {name} without defining it",
+						path.display()
+					))
+				})?;
+			let name = if prefix.is_empty() {
+				name
+			} else {
+				format!("{prefix}/{name}")
+			};
+			self.env.def(name, value);
+		}
+		Ok(Expression::Null)
+	}
+
+	// Evaluates the module at `path` in globals of its own, once per canonical path.
+	fn import_module(&mut self, path: &Path) -> Result<usize, LockjawRuntimeError> {
+		let path = self.find(path)?;
+		if let Some(&id) = self.module_paths.get(&path) {
+			return Ok(id);
+		}
+		let (id, result) = self.in_new_module(|evaluator| evaluator.load_canonical(path.clone()));
+		result?;
+		if self.modules[id].exports.is_none() {
+			return Err(LockjawRuntimeError::InvalidArguments(format!(
+				"{} does not declare its exports with module",
+				path.display()
+			)));
+		}
+		self.module_paths.insert(path, id);
+		Ok(id)
+	}
+
+	// Runs `body` with a fresh module's globals in `env`.
+	fn in_new_module<T>(
+		&mut self,
+		body: impl FnOnce(&mut Self) -> Result<T, LockjawRuntimeError>,
+	) -> (usize, Result<T, LockjawRuntimeError>) {
+		let id = self.modules.len();
+		self.modules.push(Module {
+			globals: self.builtins.clone(),
+			exports: None,
+			required: HashSet::new(),
+		});
+		let previous = self.enter_module(id);
+		let result = body(self);
+		self.enter_module(previous);
+		(id, result)
+	}
+
+	// The prelude is a module of its own, so its functions keep working however
+	// the program rebinds their names. Its definitions are copied into the
+	// program's globals and every module's starting globals.
+	fn load_prelude(&mut self) {
+		let (id, result) =
+			self.in_new_module(|evaluator| evaluator.load_reader(PRELUDE.as_bytes()));
+		result.expect("the bundled prelude evaluates");
+		self.builtins = self.modules[id].globals.clone();
+		for (name, value) in &self.builtins {
+			self.env.def(name.clone(), value.clone());
+		}
+		self.steps = 0;
+	}
+
+	// Makes `id` the module whose globals are in `env`, returning the one it replaced.
+	fn enter_module(&mut self, id: usize) -> usize {
+		let previous = self.current_module;
+		if id != previous {
+			self.env.swap_globals(&mut self.modules[previous].globals);
+			self.env.swap_globals(&mut self.modules[id].globals);
+			self.current_module = id;
+		}
+		previous
+	}
+
+	// Functions made by builtins belong to the module that was being evaluated.
+	fn claim(&self, result: Expression) -> Expression {
+		match result {
+			Expression::Atom(Atom::Value(Value::UserDef(mut func))) if func.module.is_none() => {
+				func.module = Some(self.current_module);
+				Expression::Atom(Atom::Value(Value::UserDef(func)))
+			}
+			result => result,
+		}
+	}
+
+	fn deftest(
+		&mut self,
+		mut args: VecDeque<Expression>,
+	) -> Result<Expression, LockjawRuntimeError> {
+		match (args.pop_front(), args.pop_front(), args.is_empty()) {
+			(
+				Some(Expression::Atom(Atom::String(name))),
+				Some(Expression::QExpression(body)),
+				true,
+			) => {
+				self.tests.push((name, body));
+				Ok(Expression::Null)
+			}
+			_ => Err(LockjawRuntimeError::InvalidArguments(String::from(
+				"deftest takes a name and a list of forms.",
+			))),
+		}
+	}
+
+	fn assert_error(
+		&mut self,
+		mut args: VecDeque<Expression>,
+	) -> Result<Expression, LockjawRuntimeError> {
+		if args.len() != 1 {
+			return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+				"assert-error takes a single quoted expression.",
+			)));
+		}
+		let expression = args.pop_front().unwrap().get_from_q_expression()?;
+		match self.resolve_sexpression(expression) {
+			Ok(value) => Err(LockjawRuntimeError::AssertionFailed(format!(
+				"expected an error, got {}",
+				value.write()
+			))),
+			Err(LockjawRuntimeError::Exit(code)) => Err(LockjawRuntimeError::Exit(code)),
+			Err(_) => Ok(Expression::Null),
+		}
+	}
+
+	/// Names of the tests defined with `deftest` so far, in definition order.
+	pub fn test_names(&self) -> Vec<&str> {
+		self.tests.iter().map(|(name, _)| name.as_str()).collect()
+	}
+
+	/// Evaluates each form of the `index`th test in turn, stopping at the first error.
+	pub fn run_test(&mut self, index: usize) -> Result<(), LockjawRuntimeError> {
+		let (_, body) = self.tests[index].clone();
+		for form in body {
+			self.evaluate(form)?;
+		}
+		Ok(())
+	}
+
+	/// Evaluates every top level expression of `input`, each one is evaluated
+	/// before the next is read.
+	pub fn load_reader<R: BufRead>(&mut self, input: R) -> Result<Expression, LockjawRuntimeError> {
+		let mut reader = Reader::new(input);
+		while let Some(expression) = reader.read_with(Some(self)) {
+			self.evaluate(expression?)?;
+		}
+		Ok(Expression::Null)
+	}
+
+	/// Parses every expression in `text`, expanding this evaluator's reader macros.
+	pub fn parse(&mut self, text: &str) -> Result<Expression, ParsingError> {
+		let lexemes: Vec<Lexeme> = Lexer::new(text).collect::<Result<_, LexingError>>()?;
+		Parser::new(&lexemes).with_reader_macros(self).parse_all()
+	}
+
+	/// Sorted names of every builtin and definition currently in scope.
+	pub fn bound_names(&self) -> Vec<String> {
+		let mut names: Vec<String> = self.env.names().map(String::from).collect();
+		names.sort_unstable();
+		names.dedup();
+		names
+	}
+
+	/// Binds `name` globally to `value`, as `def` does.
+	pub fn bind(&mut self, name: &str, value: Expression) {
+		self.env
+			.def(name.to_string(), Value::Variable(Box::new(value)));
+	}
+
+	/// What `name` is bound to in the current scope.
+	pub fn lookup(&self, name: &str) -> Option<&Value> {
+		self.env.get(name.to_string())
+	}
+
+	/// Number of expressions evaluated so far, a measure of work that doesn't
+	/// depend on the machine.
+	pub fn steps(&self) -> u64 {
+		self.steps
+	}
+
+	/// How a call to `name` looks, `None` unless it is bound to a function.
+	pub fn signature(&self, name: &str) -> Option<String> {
+		let value = match self.env.get(name.to_string())? {
+			// Functions given a name with `def`.
+			Value::Variable(expression) => match expression.as_ref() {
+				Expression::Atom(Atom::Value(value)) => value,
+				_ => return None,
+			},
+			value => value,
+		};
+		match value {
+			Value::UserDef(func) => {
+				let mut signature = format!("({name}");
+				for arg in func.args.iter().skip(func.curried.len()) {
+					signature.push_str(&format!(" {arg}"));
+				}
+				signature.push(')');
+				Some(signature)
+			}
+			Value::Variable(_) => None,
+			_ => builtins::signature(name).map(String::from),
+		}
+	}
+
+	pub fn read_table_mut(&mut self) -> &mut ReadTable {
+		&mut self.read_table
+	}
+
+	// (set-reader-macro "name" handler) and (set-reader-prefix "@" handler)
+	fn set_reader_macro(
+		&mut self,
+		mut args: VecDeque<Expression>,
+		prefix: bool,
+	) -> Result<Expression, LockjawRuntimeError> {
+		if args.len() != 2 {
+			return Err(LockjawRuntimeError::InvalidArgumentCount(String::from(
+				"Reader macros take a name and a handler function.",
+			)));
+		}
+		let name = args.pop_front().unwrap().get_atom()?.get_as_string()?;
+		let handler = args.pop_front().unwrap().get_atom()?.get_as_value()?;
+		if prefix {
+			let mut chars = name.chars();
+			match (chars.next(), chars.next()) {
+				(Some(c), None) => self.read_table.set_prefix(c, handler)?,
+				_ => {
+					return Err(LockjawRuntimeError::InvalidArguments(format!(
+						"Reader macro prefix must be a single character, got {name:?}"
+					)))
 				}
-				Expression::Atom(_) => Ok(expression),
-				Expression::QExpression(_) => Ok(expression),
-				Expression::Null => Ok(expression),
 			}
 		} else {
-			Ok(Expression::Null)
+			self.read_table.set_dispatch(&name, handler)?;
 		}
+		Ok(Expression::Null)
 	}
 
+	/// An evaluator with the builtins and the standard prelude.
 	pub fn new() -> Self {
+		let mut evaluator = Self::without_prelude();
+		evaluator.load_prelude();
+		evaluator
+	}
+
+	/// An evaluator with only the builtins implemented in Rust.
+	pub fn without_prelude() -> Self {
 		let mut env: Environment = Environment::new();
 		env.def(String::from("+"), Value::Builtin(builtins::add));
 		env.def(String::from("-"), Value::Builtin(builtins::sub));
@@ -97,13 +553,34 @@ impl Evaluator {
 		env.def(String::from("car"), Value::Builtin(builtins::car));
 		env.def(String::from("cdr"), Value::Builtin(builtins::cdr));
 		env.def(String::from("join"), Value::Builtin(builtins::join));
+		env.def(String::from("list"), Value::Builtin(builtins::list));
 
 		// These are special functions that depend on mutating self, and need to be treated special as such.
 		env.def(String::from("eval"), Value::Eval);
 		env.def(String::from("def"), Value::Def);
 		env.def(String::from("cond"), Value::Cond);
 		env.def(String::from("load"), Value::Load);
+		env.def(String::from("require"), Value::Require);
+		env.def(String::from("module"), Value::Module);
+		env.def(String::from("import"), Value::Import);
+		env.def(String::from("set-reader-macro"), Value::SetReaderMacro);
+		env.def(String::from("set-reader-prefix"), Value::SetReaderPrefix);
 		env.def(String::from("print"), Value::Builtin(builtins::print));
+		env.def(String::from("write"), Value::Builtin(builtins::write));
+		env.def(String::from("display"), Value::Builtin(builtins::display));
+		env.def(String::from("format"), Value::Builtin(builtins::format));
+		env.def(String::from("exit"), Value::Builtin(builtins::exit));
+		env.def(String::from("error"), Value::Builtin(builtins::error));
+		env.def(String::from("deftest"), Value::DefTest);
+		env.def(
+			String::from("assert-eq"),
+			Value::Builtin(builtins::assert_eq),
+		);
+		env.def(
+			String::from("assert-true"),
+			Value::Builtin(builtins::assert_true),
+		);
+		env.def(String::from("assert-error"), Value::AssertError);
 		env.def(String::from("fun"), Value::Builtin(builtins::fun));
 		env.def(String::from("null?"), Value::Builtin(builtins::null_q));
 		env.def(String::from("atom?"), Value::Builtin(builtins::atom_q));
@@ -114,6 +591,30 @@ impl Evaluator {
 		env.def(String::from("lt?"), Value::Builtin(builtins::lt_q));
 		env.def(String::from("eq?"), Value::Builtin(builtins::eq_q));
 		env.def(String::from("zero?"), Value::Builtin(builtins::zero_q));
+		env.def(
+			String::from("integer?"),
+			Value::Builtin(builtins::integer_q),
+		);
+		env.def(
+			String::from("keyword?"),
+			Value::Builtin(builtins::keyword_q),
+		);
+		env.def(
+			String::from("keyword->string"),
+			Value::Builtin(builtins::keyword_to_string),
+		);
+		env.def(
+			String::from("string->keyword"),
+			Value::Builtin(builtins::string_to_keyword),
+		);
+		env.def(
+			String::from("keyword->symbol"),
+			Value::Builtin(builtins::keyword_to_symbol),
+		);
+		env.def(
+			String::from("symbol->keyword"),
+			Value::Builtin(builtins::symbol_to_keyword),
+		);
 		env.def(
 			String::from("#f"),
 			Value::Variable(Box::new(Expression::Atom(Atom::Bool(false)))),
@@ -127,7 +628,24 @@ impl Evaluator {
 			Value::Variable(Box::new(Expression::Atom(Atom::Bool(true)))),
 		);
 
-		Evaluator { env }
+		let builtins = env.globals().clone();
+		Evaluator {
+			env,
+			read_table: ReadTable::new(),
+			call_depth: 0,
+			steps: 0,
+			loading: Vec::new(),
+			search_path: Vec::new(),
+			builtins,
+			modules: vec![Module {
+				globals: HashMap::new(),
+				exports: None,
+				required: HashSet::new(),
+			}],
+			current_module: 0,
+			module_paths: HashMap::new(),
+			tests: Vec::new(),
+		}
 	}
 
 	fn evaluate_user_func(
@@ -138,7 +656,12 @@ impl Evaluator {
 		// Evaluate if we have enough arguments.
 		match func.args.len().cmp(&(func.curried.len() + args.len())) {
 			std::cmp::Ordering::Equal => {
+				if self.call_depth >= MAX_CALL_DEPTH {
+					return Err(LockjawRuntimeError::RecursionLimitExceeded);
+				}
 				// Move into child environment
+				self.call_depth += 1;
+				let caller = func.module.map(|module| self.enter_module(module));
 				self.env.push_env();
 				let args = func.curried.iter().chain(args.iter());
 				for (arg, argv) in func.args.iter().zip(args) {
@@ -149,10 +672,16 @@ impl Evaluator {
 				}
 
 				// Evaluate Function
-				let value = self.resolve_sexpression(func.body)?;
-				// Move out of child environment
+				let value = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+					self.resolve_sexpression(func.body)
+				});
+				// Move out of child environment, even when evaluation failed
 				self.env.pop_env();
-				Ok(value)
+				if let Some(caller) = caller {
+					self.enter_module(caller);
+				}
+				self.call_depth -= 1;
+				value
 			}
 			std::cmp::Ordering::Greater => {
 				let mut curriedfunc = func;
@@ -174,7 +703,7 @@ impl Evaluator {
 		}
 
 		// Must check for Quote before any evaluation is done
-		if let Some(Expression::Atom(Atom::Symbol(ref sym))) = expressions.get(0) {
+		if let Some(Expression::Atom(Atom::Symbol(ref sym))) = expressions.front() {
 			if sym == "quote" {
 				expressions.pop_front();
 				return Ok(Expression::QExpression(expressions));
@@ -193,14 +722,30 @@ impl Evaluator {
 		}
 
 		let val = evals.pop_front().unwrap().get_atom()?.get_as_value()?;
+		self.apply(val, evals)
+	}
+
+	/// Calls the function `val` with already evaluated arguments.
+	pub fn apply(
+		&mut self,
+		val: Value,
+		mut evals: VecDeque<Expression>,
+	) -> Result<Expression, LockjawRuntimeError> {
 		match val {
-			Value::Builtin(f) => f(evals),
+			Value::Builtin(f) => f(evals).map(|result| self.claim(result)),
 			Value::Eval => {
 				self.resolve_sexpression(evals.pop_front().unwrap().get_from_q_expression()?)
 			}
 			Value::Def => self.def(evals),
 			Value::Cond => self.cond(evals),
 			Value::Load => self.load(evals),
+			Value::Require => self.require(evals),
+			Value::Module => self.module(evals),
+			Value::Import => self.import(evals),
+			Value::DefTest => self.deftest(evals),
+			Value::AssertError => self.assert_error(evals),
+			Value::SetReaderMacro => self.set_reader_macro(evals, false),
+			Value::SetReaderPrefix => self.set_reader_macro(evals, true),
 			Value::Variable(_) => Err(LockjawRuntimeError::InvalidFunction(format!(
 				"Expected Function, got {}",
 				val
@@ -210,19 +755,40 @@ impl Evaluator {
 	}
 
 	fn evaluate_symbol(&self, symb: &str) -> Result<Expression, LockjawRuntimeError> {
+		if symb == FORMAT_HEAD {
+			return Ok(Expression::Atom(Atom::Value(Value::Builtin(
+				builtins::format,
+			))));
+		}
 		match self.env.get(symb.to_string()) {
 			Some(Value::Variable(e)) => Ok(*e.clone()), // Prevent nesting atoms in values in atoms
 			Some(val) => Ok(Expression::Atom(Atom::Value(val.clone()))),
-			None => Err(LockjawRuntimeError::UnboundExpression),
+			None => Err(LockjawRuntimeError::UnboundExpression(symb.to_string())),
 		}
 	}
 
 	pub fn evaluate(&mut self, expression: Expression) -> Result<Expression, LockjawRuntimeError> {
+		self.steps += 1;
 		match expression {
 			Expression::Atom(Atom::Symbol(ref symb)) => self.evaluate_symbol(symb),
 			Expression::Atom(_) => Ok(expression),
-			Expression::SExpression(expressions) => self.resolve_sexpression(expressions),
+			Expression::SExpression(expressions) => {
+				stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+					self.resolve_sexpression(expressions)
+				})
+			}
 			_ => Ok(expression),
 		}
 	}
 }
+
+impl ReaderMacros for Evaluator {
+	fn expand(
+		&mut self,
+		name: MacroName,
+		datum: Expression,
+	) -> Option<Result<Expression, LockjawRuntimeError>> {
+		let handler = self.read_table.get(name)?.clone();
+		Some(self.apply(handler, VecDeque::from([datum])))
+	}
+}
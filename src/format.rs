@@ -0,0 +1,436 @@
+use crate::numeric::Numeric;
+use crate::types::*;
+use std::collections::{HashMap, VecDeque};
+
+// Which argument a directive takes: the next unused one, one by position or one by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Selector<'t> {
+	Next,
+	Index(usize),
+	Name(&'t str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+	Left,
+	Center,
+	Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+	Display,
+	// `?`, the argument as `write` would print it.
+	Write,
+	LowerHex,
+	UpperHex,
+	Binary,
+	Octal,
+	Exponent,
+}
+
+// Everything after the `:` of a directive, `[[fill]align][+][0][width][.precision][kind]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Spec {
+	fill: char,
+	align: Option<Align>,
+	sign: bool,
+	zero: bool,
+	width: usize,
+	precision: Option<usize>,
+	kind: Kind,
+}
+
+impl Default for Spec {
+	fn default() -> Self {
+		Spec {
+			fill: ' ',
+			align: None,
+			sign: false,
+			zero: false,
+			width: 0,
+			precision: None,
+			kind: Kind::Display,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece<'t> {
+	Literal(String),
+	Directive(Selector<'t>, Spec),
+}
+
+fn template_error(message: String) -> LockjawRuntimeError {
+	LockjawRuntimeError::InvalidArguments(format!("format: {message}"))
+}
+
+fn parse_align(c: char) -> Option<Align> {
+	match c {
+		'<' => Some(Align::Left),
+		'^' => Some(Align::Center),
+		'>' => Some(Align::Right),
+		_ => None,
+	}
+}
+
+fn parse_spec(text: &str) -> Result<Spec, LockjawRuntimeError> {
+	let mut spec = Spec::default();
+	let mut rest = text;
+
+	let mut chars = rest.chars();
+	match (chars.next(), chars.next().and_then(parse_align)) {
+		(Some(fill), Some(align)) => {
+			spec.fill = fill;
+			spec.align = Some(align);
+			rest = &rest[fill.len_utf8() + 1..];
+		}
+		(Some(c), _) if parse_align(c).is_some() => {
+			spec.align = parse_align(c);
+			rest = &rest[1..];
+		}
+		_ => {}
+	}
+	if let Some(after) = rest.strip_prefix('+') {
+		spec.sign = true;
+		rest = after;
+	}
+	if let Some(after) = rest.strip_prefix('0') {
+		spec.zero = true;
+		rest = after;
+	}
+
+	let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+	let width_end = digits(rest);
+	if width_end > 0 {
+		spec.width = rest[..width_end]
+			.parse()
+			.map_err(|_| template_error(format!("width too large in `{text}`")))?;
+		rest = &rest[width_end..];
+	}
+	if let Some(after) = rest.strip_prefix('.') {
+		let precision_end = digits(after);
+		if precision_end == 0 {
+			return Err(template_error(format!("missing precision in `{text}`")));
+		}
+		spec.precision = Some(
+			after[..precision_end]
+				.parse()
+				.map_err(|_| template_error(format!("precision too large in `{text}`")))?,
+		);
+		rest = &after[precision_end..];
+	}
+
+	spec.kind = match rest {
+		"" => Kind::Display,
+		"?" => Kind::Write,
+		"x" => Kind::LowerHex,
+		"X" => Kind::UpperHex,
+		"b" => Kind::Binary,
+		"o" => Kind::Octal,
+		"e" => Kind::Exponent,
+		_ => return Err(template_error(format!("invalid format spec `{text}`"))),
+	};
+	Ok(spec)
+}
+
+fn parse_directive(text: &str) -> Result<Piece<'_>, LockjawRuntimeError> {
+	let (selector, spec) = match text.split_once(':') {
+		Some((selector, spec)) => (selector, parse_spec(spec)?),
+		None => (text, Spec::default()),
+	};
+	let selector = match selector {
+		"" => Selector::Next,
+		s if s.starts_with(|c: char| c.is_ascii_digit()) => Selector::Index(
+			s.parse()
+				.map_err(|_| template_error(format!("invalid argument index `{s}`")))?,
+		),
+		name => Selector::Name(name),
+	};
+	Ok(Piece::Directive(selector, spec))
+}
+
+fn parse_template(template: &str) -> Result<Vec<Piece<'_>>, LockjawRuntimeError> {
+	let mut pieces = Vec::new();
+	let mut literal = String::new();
+	let mut chars = template.char_indices().peekable();
+	while let Some((i, c)) = chars.next() {
+		match c {
+			'{' if chars.next_if(|(_, c)| *c == '{').is_some() => literal.push('{'),
+			'}' if chars.next_if(|(_, c)| *c == '}').is_some() => literal.push('}'),
+			'{' => {
+				let close = template[i..]
+					.find('}')
+					.map(|close| i + close)
+					.ok_or_else(|| template_error(format!("unclosed `{{` at index {i}")))?;
+				if !literal.is_empty() {
+					pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+				}
+				pieces.push(parse_directive(&template[i + 1..close])?);
+				while chars.next_if(|(j, _)| *j <= close).is_some() {}
+			}
+			'}' => return Err(template_error(format!("unmatched `}}` at index {i}"))),
+			c => literal.push(c),
+		}
+	}
+	if !literal.is_empty() {
+		pieces.push(Piece::Literal(literal));
+	}
+	Ok(pieces)
+}
+
+fn pad(text: String, spec: &Spec, default_align: Align) -> String {
+	let len = text.chars().count();
+	if len >= spec.width {
+		return text;
+	}
+	let padding = spec.width - len;
+	let (before, after) = match spec.align.unwrap_or(default_align) {
+		Align::Left => (0, padding),
+		Align::Center => (padding / 2, padding - padding / 2),
+		Align::Right => (padding, 0),
+	};
+	let fill = |n| std::iter::repeat_n(spec.fill, n);
+	fill(before)
+		.chain(text.chars())
+		.chain(fill(after))
+		.collect()
+}
+
+fn render_number(num: Numeric, spec: &Spec) -> Result<String, LockjawRuntimeError> {
+	let integer = |kind: &str| match num {
+		Numeric::Int(i) => Ok(i),
+		Numeric::Float(_) => Err(template_error(format!(
+			"`{kind}` needs an integer, got {num}"
+		))),
+	};
+	// Rust prints negative integers in these radixes as two's complement,
+	// the sign is kept apart from the digits like it is in base 10.
+	let signed = |i: i64| if i < 0 { "-" } else { "" };
+	let text = match (spec.kind, spec.precision) {
+		(Kind::LowerHex, _) => {
+			integer("x").map(|i| format!("{}{:x}", signed(i), i.unsigned_abs()))?
+		}
+		(Kind::UpperHex, _) => {
+			integer("X").map(|i| format!("{}{:X}", signed(i), i.unsigned_abs()))?
+		}
+		(Kind::Binary, _) => {
+			integer("b").map(|i| format!("{}{:b}", signed(i), i.unsigned_abs()))?
+		}
+		(Kind::Octal, _) => integer("o").map(|i| format!("{}{:o}", signed(i), i.unsigned_abs()))?,
+		(Kind::Exponent, Some(precision)) => match num {
+			Numeric::Int(i) => format!("{:.precision$e}", i as f64),
+			Numeric::Float(f) => format!("{f:.precision$e}"),
+		},
+		(Kind::Exponent, None) => match num {
+			Numeric::Int(i) => format!("{i:e}"),
+			Numeric::Float(f) => format!("{f:e}"),
+		},
+		(_, Some(precision)) => match num {
+			Numeric::Int(i) => format!("{:.precision$}", i as f64),
+			Numeric::Float(f) => format!("{f:.precision$}"),
+		},
+		(_, None) => num.to_string(),
+	};
+	let (sign, digits) = match text.strip_prefix('-') {
+		Some(digits) => ("-", digits),
+		None if spec.sign => ("+", text.as_str()),
+		None => ("", text.as_str()),
+	};
+	if spec.zero {
+		let zeros = spec
+			.width
+			.saturating_sub(sign.len() + digits.chars().count());
+		return Ok(format!("{sign}{}{digits}", "0".repeat(zeros)));
+	}
+	Ok(pad(format!("{sign}{digits}"), spec, Align::Right))
+}
+
+fn render(argument: &Expression, spec: &Spec) -> Result<String, LockjawRuntimeError> {
+	match (argument, spec.kind) {
+		(_, Kind::Write) => Ok(pad(argument.write().to_string(), spec, Align::Left)),
+		(Expression::Atom(Atom::Number(num)), _) => render_number(*num, spec),
+		(_, Kind::Display) => {
+			let mut text = argument.display().to_string();
+			if let Some(precision) = spec.precision {
+				text = text.chars().take(precision).collect();
+			}
+			Ok(pad(text, spec, Align::Left))
+		}
+		_ => Err(template_error(format!(
+			"numeric format used with non-number {argument}"
+		))),
+	}
+}
+
+/// Fills in the `{}` directives of `template` from `args`.
+///
+/// `{}` takes the next positional argument, `{2}` the third and `{name}` the
+/// value following the keyword `:name`. A keyword argument only names the
+/// value after it when the template refers to that name, otherwise it is an
+/// ordinary positional argument. `{{` and `}}` are literal braces.
+pub fn format(template: &str, args: VecDeque<Expression>) -> Result<String, LockjawRuntimeError> {
+	let pieces = parse_template(template)?;
+	let is_named = |keyword: &Keyword| {
+		pieces.iter().any(
+			|piece| matches!(piece, Piece::Directive(Selector::Name(name), _) if *name == keyword.name()),
+		)
+	};
+
+	let mut positional = Vec::new();
+	let mut named = HashMap::new();
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		match arg {
+			Expression::Atom(Atom::Keyword(keyword)) if is_named(&keyword) => {
+				let value = args
+					.next()
+					.ok_or_else(|| template_error(format!("no value given for {keyword}")))?;
+				named.insert(keyword.name().to_string(), value);
+			}
+			arg => positional.push(arg),
+		}
+	}
+
+	let mut output = String::new();
+	let mut next = 0;
+	for piece in &pieces {
+		let (selector, spec) = match piece {
+			Piece::Literal(text) => {
+				output.push_str(text);
+				continue;
+			}
+			Piece::Directive(selector, spec) => (selector, spec),
+		};
+		let argument = match *selector {
+			Selector::Next => {
+				next += 1;
+				positional.get(next - 1).ok_or_else(|| {
+					template_error(format!("not enough arguments, {} given", positional.len()))
+				})?
+			}
+			Selector::Index(i) => positional.get(i).ok_or_else(|| {
+				template_error(format!(
+					"no argument {i}, {} positional arguments given",
+					positional.len()
+				))
+			})?,
+			Selector::Name(name) => named
+				.get(name)
+				.ok_or_else(|| template_error(format!("no argument named :{name}")))?,
+		};
+		output.push_str(&render(argument, spec)?);
+	}
+	Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn int(i: i64) -> Expression {
+		Expression::Atom(Atom::Number(Numeric::Int(i)))
+	}
+
+	fn float(f: f64) -> Expression {
+		Expression::Atom(Atom::Number(Numeric::Float(f)))
+	}
+
+	fn string(s: &str) -> Expression {
+		Expression::Atom(Atom::String(s.to_string()))
+	}
+
+	fn keyword(name: &str) -> Expression {
+		Expression::Atom(Atom::Keyword(Keyword::new(name)))
+	}
+
+	fn format_ok(template: &str, args: Vec<Expression>) -> String {
+		format(template, VecDeque::from(args)).unwrap()
+	}
+
+	#[test]
+	fn positional_and_indexed_arguments() {
+		assert_eq!("1 2 1", format_ok("{} {} {0}", vec![int(1), int(2)]));
+		assert_eq!("{x} }", format_ok("{{x}} }}", vec![]));
+	}
+
+	#[test]
+	fn named_arguments_follow_their_keyword() {
+		assert_eq!(
+			"hi bob :x",
+			format_ok(
+				"{greeting} {} {}",
+				vec![
+					keyword("greeting"),
+					string("hi"),
+					string("bob"),
+					keyword("x")
+				]
+			)
+		);
+	}
+
+	#[test]
+	fn width_alignment_and_fill() {
+		assert_eq!("ab   |", format_ok("{:5}|", vec![string("ab")]));
+		assert_eq!("   42|", format_ok("{:5}|", vec![int(42)]));
+		assert_eq!("*ab**", format_ok("{:*^5}", vec![string("ab")]));
+		assert_eq!("-0042", format_ok("{:05}", vec![int(-42)]));
+		assert_eq!("abc", format_ok("{:.3}", vec![string("abcdef")]));
+	}
+
+	#[test]
+	fn number_formatting() {
+		assert_eq!(" 1234.57", format_ok("{:>8.2}", vec![float(1234.5678)]));
+		assert_eq!("+7", format_ok("{:+}", vec![int(7)]));
+		assert_eq!(
+			"ff FF 101 17",
+			format_ok("{:x} {0:X} {1:b} {2:o}", vec![int(255), int(5), int(15)])
+		);
+		assert_eq!("1.5e3", format_ok("{:e}", vec![float(1500.0)]));
+		assert_eq!("2.0", format_ok("{}", vec![float(2.0)]));
+	}
+
+	#[test]
+	fn negative_integers_keep_their_sign_in_other_radixes() {
+		assert_eq!(
+			"-ff -FF -101 -17",
+			format_ok(
+				"{:x} {:X} {:b} {:o}",
+				vec![int(-255), int(-255), int(-5), int(-15)]
+			)
+		);
+		assert_eq!("-000a", format_ok("{:05x}", vec![int(-10)]));
+		assert_eq!("-8000000000000000", format_ok("{:x}", vec![int(i64::MIN)]));
+	}
+
+	#[test]
+	fn debug_directive_uses_the_write_printer() {
+		assert_eq!(
+			"\"a\\nb\" a\nb",
+			format_ok("{:?} {0}", vec![string("a\nb")])
+		);
+	}
+
+	#[test]
+	fn template_errors() {
+		for (template, args) in [
+			("{", vec![]),
+			("}", vec![]),
+			("{}", vec![]),
+			("{1}", vec![int(1)]),
+			("{name}", vec![]),
+			("{:x}", vec![float(1.5)]),
+			("{:q}", vec![int(1)]),
+			("{:x}", vec![string("a")]),
+		] {
+			assert!(
+				matches!(
+					format(template, VecDeque::from(args)),
+					Err(LockjawRuntimeError::InvalidArguments(_))
+				),
+				"{template}"
+			);
+		}
+	}
+}
@@ -0,0 +1,366 @@
+//! Front end only modes: checking syntax and dumping what the lexer and parser produce.
+
+use std::error::Error;
+use std::fmt::{Display, Write as _};
+use std::io::{self, Read};
+use std::path::Path;
+
+use lockjaw::lexer::{Lexeme, LexemeType, Lexer, LexingError};
+use lockjaw::numeric::Numeric;
+use lockjaw::parser::{Parser, ParsingError};
+use lockjaw::readtable::{MacroName, ReaderMacros};
+use lockjaw::types::{Atom, Expression, LockjawRuntimeError};
+use serde_json::{json, Value};
+
+use crate::repl::print_error;
+
+/// How `--dump-tokens` and `--dump-ast` print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+	Readable,
+	Json,
+}
+
+// Reader macros are only known once the program runs, so without evaluating
+// anything every macro is accepted and left in the tree as `(name datum)`.
+struct Unexpanded;
+
+impl ReaderMacros for Unexpanded {
+	fn expand(
+		&mut self,
+		name: MacroName,
+		datum: Expression,
+	) -> Option<Result<Expression, LockjawRuntimeError>> {
+		Some(Ok(Expression::SExpression(
+			[Expression::Atom(Atom::Symbol(name.to_string())), datum].into(),
+		)))
+	}
+}
+
+fn read_source(path: &Path) -> io::Result<String> {
+	if path == Path::new("-") {
+		let mut text = String::new();
+		io::stdin().lock().read_to_string(&mut text)?;
+		Ok(text)
+	} else {
+		std::fs::read_to_string(path)
+	}
+}
+
+// One based line and column (in characters) of a byte offset into `text`.
+fn line_column(text: &str, index: usize) -> (usize, usize) {
+	let mut index = index.min(text.len());
+	while !text.is_char_boundary(index) {
+		index -= 1;
+	}
+	let before = &text[..index];
+	let line = before.matches('\n').count() + 1;
+	let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+	(line, column)
+}
+
+fn lex(text: &str) -> Result<Vec<Lexeme<'_>>, LexingError> {
+	Lexer::new(text).collect()
+}
+
+fn parse(text: &str) -> Result<Expression, ParsingError> {
+	let lexemes = lex(text)?;
+	Parser::new(&lexemes)
+		.with_reader_macros(&mut Unexpanded)
+		.parse_all()
+}
+
+// `path:line:column: message`, for an error at byte offset `index` into `text`.
+fn report(path: &Path, text: &str, index: usize, error: &ParsingError) {
+	let (line, column) = line_column(text, index);
+	print_error(&format!("{}:{line}:{column}: {error}", path.display()));
+}
+
+// The lexemes of `text[start..end]`, positioned within the whole of `text`.
+fn lex_form(text: &str, start: usize, end: usize) -> Lexer<'_> {
+	if start == 0 {
+		Lexer::new(&text[..end])
+	} else {
+		Lexer::resume_at(&text[..end], start)
+	}
+}
+
+// Where the last top-level bracket left open in `lexemes` was opened.
+fn unclosed_form(lexemes: &[Lexeme]) -> Option<usize> {
+	let mut depth = 0usize;
+	let mut opened = None;
+	for lexeme in lexemes {
+		match lexeme.value {
+			LexemeType::LeftParen | LexemeType::LeftCBracket => {
+				if depth == 0 {
+					opened = Some(lexeme.index);
+				}
+				depth += 1;
+			}
+			LexemeType::RightParen | LexemeType::RightCBracket => depth = depth.saturating_sub(1),
+			_ => {}
+		}
+	}
+	opened.filter(|_| depth > 0)
+}
+
+// The first line after the one holding byte offset `after` that starts a
+// top-level form, one starting in the first column with something other than
+// a closing bracket or a comment.
+fn next_form(text: &str, after: usize) -> Option<usize> {
+	let mut start = after + text[after..].find('\n')? + 1;
+	loop {
+		match text[start..].chars().next() {
+			Some(c) if !c.is_whitespace() && !matches!(c, ';' | ')' | '}') => return Some(start),
+			Some(_) => start += text[start..].find('\n')? + 1,
+			None => return None,
+		}
+	}
+}
+
+// Every syntax error in `text`, with the byte offset it points at. A file that
+// doesn't parse is checked again one top-level form at a time, taking each line
+// that starts in the first column as the start of a form, so independent
+// mistakes are all reported. Errors without a position of their own point at
+// the bracket left open, or at the end of the form.
+fn syntax_errors(text: &str) -> Vec<(usize, ParsingError)> {
+	let Err(first) = parse(text) else {
+		return Vec::new();
+	};
+	let mut errors = Vec::new();
+	let mut start = 0;
+	while start < text.len() {
+		let end = next_form(text, start).unwrap_or(text.len());
+		match lex_form(text, start, end).collect::<Result<Vec<_>, _>>() {
+			Ok(lexemes) => {
+				let parsed = Parser::new(&lexemes)
+					.with_reader_macros(&mut Unexpanded)
+					.parse_all();
+				if let Err(why) = parsed {
+					let index = why.index().or_else(|| unclosed_form(&lexemes));
+					errors.push((index.unwrap_or(end), why));
+				}
+			}
+			Err(why) => errors.push((why.position(), ParsingError::from(why))),
+		}
+		start = end;
+	}
+	if errors.is_empty() {
+		errors.push((first.index().unwrap_or(text.len()), first));
+	}
+	errors
+}
+
+fn report_io(path: &Path, error: &dyn Display) {
+	print_error(&format!("{}: {error}", path.display()));
+}
+
+/// Lexes and parses every file without evaluating anything, reporting every
+/// syntax error found. Returns true when every file is well formed.
+pub fn check(paths: &[&Path]) -> bool {
+	let mut ok = true;
+	for path in paths {
+		match read_source(path) {
+			Ok(text) => {
+				for (index, why) in syntax_errors(&text) {
+					report(path, &text, index, &why);
+					ok = false;
+				}
+			}
+			Err(why) => {
+				report_io(path, &why);
+				ok = false;
+			}
+		}
+	}
+	ok
+}
+
+fn lexeme_json(text: &str, lexeme: &Lexeme) -> Value {
+	let (line, column) = line_column(text, lexeme.index);
+	let (kind, contents) = match lexeme.value {
+		LexemeType::RightParen => ("RightParen", None),
+		LexemeType::LeftParen => ("LeftParen", None),
+		LexemeType::RightCBracket => ("RightCBracket", None),
+		LexemeType::LeftCBracket => ("LeftCBracket", None),
+		LexemeType::Integer(s) => ("Integer", Some(s.to_string())),
+		LexemeType::Float(s) => ("Float", Some(s.to_string())),
+		LexemeType::RawSymbol(s) => ("RawSymbol", Some(s.to_string())),
+		LexemeType::Prefix(c) => ("Prefix", Some(c.to_string())),
+		LexemeType::Dispatch(s) => ("Dispatch", Some(s.to_string())),
+		LexemeType::Keyword(s) => ("Keyword", Some(s.to_string())),
+		LexemeType::StringLiteral(s) => ("StringLiteral", Some(s.to_string())),
+		LexemeType::RawStringLiteral(s) => ("RawStringLiteral", Some(s.to_string())),
+		LexemeType::MultilineStringLiteral(s) => ("MultilineStringLiteral", Some(s.to_string())),
+		LexemeType::InterpolatedStringLiteral(s) => {
+			("InterpolatedStringLiteral", Some(s.to_string()))
+		}
+	};
+	json!({
+		"index": lexeme.index,
+		"line": line,
+		"column": column,
+		"kind": kind,
+		"value": contents,
+	})
+}
+
+fn expression_json(expression: &Expression) -> Value {
+	let value = match expression {
+		Expression::Atom(Atom::Number(Numeric::Int(i))) => json!(i),
+		Expression::Atom(Atom::Number(Numeric::Float(f))) => json!(f),
+		Expression::Atom(Atom::String(s)) | Expression::Atom(Atom::Symbol(s)) => json!(s),
+		Expression::Atom(Atom::Keyword(k)) => json!(k.name()),
+		Expression::Atom(Atom::Bool(b)) => json!(b),
+		Expression::Atom(Atom::Value(_)) => json!(expression.write().to_string()),
+		Expression::SExpression(items) | Expression::QExpression(items) => {
+			Value::Array(items.iter().map(expression_json).collect())
+		}
+		Expression::Null => Value::Null,
+	};
+	json!({ "type": expression.type_name(), "value": value })
+}
+
+/// Prints the lexemes of the file, up to the first lexing error.
+pub fn dump_tokens(path: &Path, format: DumpFormat) -> Result<(), Box<dyn Error>> {
+	let text = read_source(path).inspect_err(|why| report_io(path, why))?;
+	let mut lexemes = Vec::new();
+	let mut failure = None;
+	for lexeme in Lexer::new(&text) {
+		match lexeme {
+			Ok(lexeme) => lexemes.push(lexeme),
+			Err(why) => failure = Some(why),
+		}
+	}
+	match format {
+		DumpFormat::Readable => {
+			for lexeme in &lexemes {
+				let (line, column) = line_column(&text, lexeme.index);
+				println!("{line:>4}:{column:<4} {:?}", lexeme.value);
+			}
+		}
+		DumpFormat::Json => {
+			let tokens: Vec<Value> = lexemes.iter().map(|l| lexeme_json(&text, l)).collect();
+			println!("{:#}", Value::Array(tokens));
+		}
+	}
+	match failure {
+		Some(why) => {
+			let index = why.position();
+			let why = ParsingError::from(why);
+			report(path, &text, index, &why);
+			Err(why.into())
+		}
+		None => Ok(()),
+	}
+}
+
+// One node per line, indented under the list holding it, atoms written as
+// they would be read.
+fn tree(expression: &Expression, depth: usize, out: &mut String) {
+	let indent = "  ".repeat(depth);
+	match expression {
+		Expression::SExpression(items) | Expression::QExpression(items) => {
+			let _ = writeln!(out, "{indent}{}", expression.type_name());
+			for item in items {
+				tree(item, depth + 1, out);
+			}
+		}
+		_ => {
+			let _ = writeln!(
+				out,
+				"{indent}{} {}",
+				expression.type_name(),
+				expression.write()
+			);
+		}
+	}
+}
+
+/// Prints the expression tree the file parses to, with reader macros left unexpanded.
+pub fn dump_ast(path: &Path, format: DumpFormat) -> Result<(), Box<dyn Error>> {
+	let text = read_source(path).inspect_err(|why| report_io(path, why))?;
+	let parsed = parse(&text)
+		.inspect_err(|why| report(path, &text, why.index().unwrap_or(text.len()), why))?;
+	match format {
+		DumpFormat::Readable => {
+			let mut out = String::new();
+			tree(&parsed, 0, &mut out);
+			print!("{out}");
+		}
+		DumpFormat::Json => println!("{:#}", expression_json(&parsed)),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn line_column_counts_from_one() {
+		let text = "(+ 1\n  2)\n";
+		assert_eq!(line_column(text, 0), (1, 1));
+		assert_eq!(line_column(text, 3), (1, 4));
+		assert_eq!(line_column(text, 7), (2, 3));
+		assert_eq!(line_column(text, text.len()), (3, 1));
+		assert_eq!(line_column("é x", 3), (1, 3));
+		assert_eq!(line_column("é x", 1), (1, 1));
+	}
+
+	#[test]
+	fn unknown_reader_macros_still_parse() {
+		assert_eq!(
+			parse("#inc(1)").unwrap().write().to_string(),
+			"((#inc (1)))"
+		);
+		assert!(parse("(+ 1").is_err());
+	}
+
+	#[test]
+	fn check_reports_every_independent_syntax_error() {
+		let text =
+			"(def {a} \"\\q\")\n(def {b} (+ 1 2)\n(def {c} 3)\n; {\n(def {d} {1 2)\n(def {e} 5)\n";
+		let errors: Vec<(usize, usize)> = syntax_errors(text)
+			.into_iter()
+			.map(|(index, _)| line_column(text, index))
+			.collect();
+		assert_eq!(errors, [(1, 11), (2, 1), (5, 14)]);
+		assert!(syntax_errors("(def {a}\n  1)\n(b)\n").is_empty());
+		assert_eq!(syntax_errors("(a\n").len(), 1);
+	}
+
+	#[test]
+	fn trees_print_one_node_per_line() {
+		let mut out = String::new();
+		tree(&parse("(f \"s\") :k").unwrap(), 0, &mut out);
+		assert_eq!(
+			out,
+			"SExpression\n  SExpression\n    Symbol f\n    String \"s\"\n  Keyword :k\n"
+		);
+	}
+
+	#[test]
+	fn expressions_serialize_with_their_type() {
+		assert_eq!(
+			expression_json(&parse("{a :k 1.5}").unwrap()),
+			json!({"type": "SExpression", "value": [
+				{"type": "QExpression", "value": [
+					{"type": "Symbol", "value": "a"},
+					{"type": "Keyword", "value": "k"},
+					{"type": "Float", "value": 1.5},
+				]},
+			]})
+		);
+	}
+
+	#[test]
+	fn lexemes_serialize_with_their_location() {
+		let text = "\n:key";
+		let lexemes = lex(text).unwrap();
+		assert_eq!(
+			lexeme_json(text, &lexemes[0]),
+			json!({"index": 1, "line": 2, "column": 1, "kind": "Keyword", "value": "key"})
+		);
+	}
+}
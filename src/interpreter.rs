@@ -0,0 +1,170 @@
+//! A small facade over `Evaluator` for programs embedding Lockjaw.
+
+use crate::evaluator::Evaluator;
+use crate::reader::Reader;
+use crate::types::*;
+use crate::Error;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+/// A Lockjaw interpreter with the builtins and, unless left out, the prelude.
+///
+/// ```
+/// use lockjaw::{Expression, Interpreter};
+///
+/// let mut lj = Interpreter::new();
+/// lj.eval_str("(def {square} (fun {x} {* x x}))").unwrap();
+/// lj.define("side", 3);
+/// assert_eq!(lj.eval_str("(square side)").unwrap(), Expression::from(9));
+/// assert_eq!(lj.call("square", [Expression::from(4)]).unwrap(), Expression::from(16));
+/// ```
+pub struct Interpreter {
+	evaluator: Evaluator,
+}
+
+impl Default for Interpreter {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Interpreter {
+	/// An interpreter with the builtins and the standard prelude.
+	pub fn new() -> Self {
+		Interpreter {
+			evaluator: Evaluator::new(),
+		}
+	}
+
+	/// An interpreter with only the builtins implemented in Rust.
+	pub fn without_prelude() -> Self {
+		Interpreter {
+			evaluator: Evaluator::without_prelude(),
+		}
+	}
+
+	/// Directories searched for libraries named by `load`, `require` and `import`.
+	pub fn set_search_path(&mut self, directories: Vec<PathBuf>) {
+		self.evaluator.set_search_path(directories);
+	}
+
+	/// Evaluates each top level expression of `source` in turn, as a file is
+	/// evaluated, and returns the value of the last one.
+	pub fn eval_str(&mut self, source: &str) -> Result<Expression, Error> {
+		self.eval_reader(source.as_bytes())
+	}
+
+	/// Evaluates each top level expression read from `input`, returning the
+	/// value of the last one.
+	pub fn eval_reader<R: BufRead>(&mut self, input: R) -> Result<Expression, Error> {
+		let mut reader = Reader::new(input);
+		let mut value = Expression::Null;
+		while let Some(expression) = reader.read_with(Some(&mut self.evaluator)) {
+			value = self.evaluator.evaluate(expression?)?;
+		}
+		Ok(value)
+	}
+
+	/// Evaluates the file at `path`, as `load` does.
+	pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+		self.evaluator.load_file(path.as_ref()).map(|_| ())
+	}
+
+	/// Binds `name` globally to `value`, as `def` does.
+	pub fn define(&mut self, name: &str, value: impl Into<Expression>) {
+		self.evaluator.bind(name, value.into());
+	}
+
+	/// The value `name` is bound to, `None` when it is unbound.
+	pub fn get(&self, name: &str) -> Option<Expression> {
+		match self.evaluator.lookup(name)? {
+			Value::Variable(expression) => Some(*expression.clone()),
+			value => Some(Expression::Atom(Atom::Value(value.clone()))),
+		}
+	}
+
+	/// Calls the function bound to `name` with already evaluated arguments.
+	pub fn call(
+		&mut self,
+		name: &str,
+		args: impl IntoIterator<Item = Expression>,
+	) -> Result<Expression, Error> {
+		let function = match self
+			.get(name)
+			.ok_or_else(|| Error::UnboundExpression(name.to_string()))?
+		{
+			Expression::Atom(Atom::Value(function)) => function,
+			value => {
+				return Err(Error::InvalidFunction(format!(
+					"Expected Function, got {value}"
+				)))
+			}
+		};
+		let args: VecDeque<Expression> = args.into_iter().collect();
+		self.evaluator.apply(function, args)
+	}
+
+	/// The evaluator underneath, for the `lockjaw` binary's REPL line handling.
+	#[doc(hidden)]
+	pub fn evaluator_mut(&mut self) -> &mut Evaluator {
+		&mut self.evaluator
+	}
+
+	/// Gives up the facade for the evaluator underneath, which the REPL runs on.
+	#[doc(hidden)]
+	pub fn into_evaluator(self) -> Evaluator {
+		self.evaluator
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::numeric::Numeric;
+
+	#[test]
+	fn eval_str_returns_the_last_value() {
+		let mut lj = Interpreter::new();
+		assert_eq!(
+			lj.eval_str("(def {x} 2)\n(+ x 1)").unwrap(),
+			Expression::from(3)
+		);
+		assert_eq!(lj.eval_str("").unwrap(), Expression::Null);
+		assert!(matches!(lj.eval_str("(+ x"), Err(Error::ParserError(_))));
+	}
+
+	#[test]
+	fn defined_values_are_visible_to_programs() {
+		let mut lj = Interpreter::new();
+		lj.define("name", "lockjaw");
+		lj.define("sizes", vec![Expression::from(1), Expression::from(2.5)]);
+		assert_eq!(lj.get("name"), Some(Expression::from("lockjaw")));
+		assert_eq!(
+			lj.eval_str("(car (cdr sizes))").unwrap(),
+			Expression::Atom(Atom::Number(Numeric::Float(2.5)))
+		);
+		assert_eq!(lj.get("undefined"), None);
+	}
+
+	#[test]
+	fn call_applies_functions_by_name() {
+		let mut lj = Interpreter::new();
+		assert_eq!(
+			lj.call("length", [Expression::from(vec![Expression::from(true)])])
+				.unwrap(),
+			Expression::from(1)
+		);
+		assert_eq!(
+			lj.call("+", [Expression::from(1), Expression::from(2)])
+				.unwrap(),
+			Expression::from(3)
+		);
+		assert!(matches!(
+			lj.call("nothing", []),
+			Err(Error::UnboundExpression(name)) if name == "nothing"
+		));
+		lj.define("x", 1);
+		assert!(matches!(lj.call("x", []), Err(Error::InvalidFunction(_))));
+	}
+}
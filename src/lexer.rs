@@ -23,6 +23,22 @@ pub struct LexingError {
 	kind: LexingErrorKind,
 }
 
+impl LexingError {
+	pub fn kind(&self) -> &LexingErrorKind {
+		&self.kind
+	}
+
+	pub fn position(&self) -> usize {
+		self.position
+	}
+
+	// Errors from lexing a slice of a larger text are reported relative to the whole text.
+	pub fn offset_by(mut self, offset: usize) -> Self {
+		self.position += offset;
+		self
+	}
+}
+
 impl Display for LexingError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "Error at position {}: {}", self.position, self.kind)
@@ -42,9 +58,25 @@ pub enum LexemeType<'a> {
 	Integer(&'a str),
 	Float(&'a str),
 	RawSymbol(&'a str),
+	// A reader macro applied to the expression that follows it, `Prefix` is one
+	// of `PREFIX_CHARACTERS`, `Dispatch` the name of a `#name` macro without the `#`.
+	Prefix(char),
+	Dispatch(&'a str),
+	// The name of the keyword, without the leading `:`.
+	Keyword(&'a str),
+	// String contents between the delimiters, escapes are left for the parser.
 	StringLiteral(&'a str),
+	// Contents of `#r"..."`, taken as written.
+	RawStringLiteral(&'a str),
+	// Contents of `"""..."""`, before escapes are processed and indentation is stripped.
+	MultilineStringLiteral(&'a str),
+	// Contents of `$"..."`, embedded expressions are left for the parser.
+	InterpolatedStringLiteral(&'a str),
 }
 
+/// Characters with no meaning of their own, free to be claimed by reader macros.
+pub const PREFIX_CHARACTERS: [char; 7] = ['`', ',', '@', '~', '^', '%', '$'];
+
 #[derive(Debug, Clone, Copy)]
 pub struct Lexeme<'a> {
 	pub index: usize,
@@ -60,7 +92,24 @@ pub struct Lexer<'a> {
 }
 
 impl<'a> Lexer<'a> {
+	/// Lexes `text` as the start of a program, so a byte order mark and a
+	/// `#!` interpreter line before anything else are skipped.
 	pub fn new(text: &'a str) -> Self {
+		let mut lexer = Self::resume(text);
+		// A byte order mark is only meaningful at the very start of the text.
+		if text.starts_with('\u{FEFF}') {
+			lexer.index = '\u{FEFF}'.len_utf8();
+		}
+		if text[lexer.index..].starts_with("#!") {
+			lexer.index += text[lexer.index..]
+				.find('\n')
+				.unwrap_or(text.len() - lexer.index);
+		}
+		lexer
+	}
+
+	/// Lexes `text` that carries on from earlier input, nothing is skipped.
+	pub fn resume(text: &'a str) -> Self {
 		Lexer {
 			text,
 			index: 0,
@@ -68,76 +117,240 @@ impl<'a> Lexer<'a> {
 		}
 	}
 
+	/// Lexes `text` from byte offset `start` on, positions still count from the
+	/// start of `text`.
+	pub fn resume_at(text: &'a str, start: usize) -> Self {
+		Lexer {
+			index: start,
+			..Self::resume(text)
+		}
+	}
+
+	/// Byte offset just past the last lexeme returned.
+	pub fn position(&self) -> usize {
+		self.index
+	}
+
 	fn advance_char(&mut self) -> Option<char> {
-		self.index += 1;
-		self.text[self.index - 1..].chars().next()
+		let c = self.text.get(self.index..)?.chars().next()?;
+		self.index += c.len_utf8();
+		Some(c)
 	}
 
-	fn lex_number(&mut self) -> Result<LexemeType<'a>, LexingError> {
-		let numstart = self.index - 1;
-		if let Some((num_split, nextchar)) = self.text[numstart..]
-			.chars()
-			.enumerate()
-			.find(|(_, x)| !x.is_ascii_digit())
-		{
-			if nextchar == '.' {
-				if let Some((float_end, _)) = self.text[numstart + num_split + 1..]
-					.char_indices()
-					.find(|(_, x)| !x.is_ascii_digit())
-				{
-					self.index += num_split + float_end + 1;
-					Ok(LexemeType::Float(
-						&self.text[numstart..numstart + num_split + float_end + 1],
-					))
-				} else {
+	// Numbers and symbols both run until the next delimiter, `.` is included so
+	// that floats are a single token.
+	fn token_end(&self, start: usize) -> usize {
+		self.text[start..]
+			.find(|x| x != '.' && !Self::is_symbol_continue(x))
+			.map_or(self.text.len(), |i| start + i)
+	}
+
+	// A token that starts with a digit, or a sign followed by a digit, must be a
+	// number in its entirety. `1+` is an error rather than `1` followed by `+`.
+	fn lex_number(&mut self, start: usize) -> Result<LexemeType<'a>, LexingError> {
+		let end = self.token_end(start);
+		let token = &self.text[start..end];
+		let unsigned = token.strip_prefix(['+', '-']).unwrap_or(token);
+		let (integral, fractional) = match unsigned.split_once('.') {
+			Some((integral, fractional)) => (integral, Some(fractional)),
+			None => (unsigned, None),
+		};
+		let all_digits = |x: &str| x.chars().all(|c| c.is_ascii_digit());
+		if integral.is_empty() || !all_digits(integral) || !fractional.is_none_or(all_digits) {
+			return Err(LexingError {
+				position: start,
+				kind: LexingErrorKind::InvalidLiteral {
+					expected: String::from("a number"),
+					got: token.to_string(),
+				},
+			});
+		}
+		self.index = end;
+		Ok(match fractional {
+			Some(_) => LexemeType::Float(token),
+			None => LexemeType::Integer(token),
+		})
+	}
+
+	fn is_symbol_punctuation(c: char) -> bool {
+		matches!(
+			c,
+			'/' | '_' | '+' | '-' | '*' | '\\' | '=' | '>' | '<' | '!' | '&' | '?' | '#'
+		)
+	}
+
+	fn is_symbol_start(c: char) -> bool {
+		unicode_xid::UnicodeXID::is_xid_start(c) || Self::is_symbol_punctuation(c)
+	}
+
+	pub fn is_symbol_continue(c: char) -> bool {
+		unicode_xid::UnicodeXID::is_xid_continue(c) || Self::is_symbol_punctuation(c)
+	}
+
+	fn lex_raw_symbol(&mut self, start: usize) -> LexemeType<'a> {
+		self.index = self.text[start..]
+			.find(|x| !Self::is_symbol_continue(x))
+			.map_or(self.text.len(), |i| start + i);
+		let symbol = &self.text[start..self.index];
+		// `#name` directly followed by a string or bracket is a dispatch macro, not a symbol.
+		match symbol.strip_prefix('#') {
+			Some(name)
+				if !name.is_empty()
+					&& self.text[self.index..].starts_with(['"', '\'', '(', '{']) =>
+			{
+				LexemeType::Dispatch(name)
+			}
+			_ => LexemeType::RawSymbol(symbol),
+		}
+	}
+
+	fn lex_keyword(&mut self, start: usize) -> Result<LexemeType<'a>, LexingError> {
+		let name_start = start + 1;
+		self.index = self.text[name_start..]
+			.find(|x| !Self::is_symbol_continue(x))
+			.map_or(self.text.len(), |i| name_start + i);
+		if self.index == name_start {
+			return Err(LexingError {
+				position: start,
+				kind: LexingErrorKind::InvalidLiteral {
+					expected: String::from("a keyword name after `:`"),
+					got: self.text[name_start..].chars().take(1).collect(),
+				},
+			});
+		}
+		Ok(LexemeType::Keyword(&self.text[name_start..self.index]))
+	}
+
+	// Skips a `#| ... |#` comment, nested block comments must be balanced.
+	fn skip_block_comment(&mut self, start: usize) -> Result<(), LexingError> {
+		let mut depth = 1;
+		// Step past the `|` of the opening `#|`.
+		self.index += 1;
+		while depth > 0 {
+			let rest = &self.text[self.index.min(self.text.len())..];
+			match rest.find(['#', '|']) {
+				Some(i) if rest[i..].starts_with("#|") => {
+					depth += 1;
+					self.index += i + 2;
+				}
+				Some(i) if rest[i..].starts_with("|#") => {
+					depth -= 1;
+					self.index += i + 2;
+				}
+				Some(i) => self.index += i + 1,
+				None => {
 					self.index = self.text.len();
-					Ok(LexemeType::Float(&self.text[numstart..]))
+					return Err(LexingError {
+						position: start,
+						kind: LexingErrorKind::UnexpectedEof,
+					});
 				}
-			} else {
-				self.index = numstart + num_split;
-				Ok(LexemeType::Integer(
-					&self.text[numstart..numstart + num_split],
-				))
 			}
-		} else {
-			self.index = self.text.len();
-			Ok(LexemeType::Integer(&self.text[numstart..]))
 		}
+		Ok(())
 	}
 
-	fn is_valid_raw_symbol(c: char) -> bool {
-		match c {
-			'a'..='z' => true,
-			'A'..='Z' => true,
-			// '0'..='9' => true, // Might turn on eventually, not yet.
-			'/' | '_' | '+' | '-' | '*' | '\\' | '=' | '>' | '<' | '!' | '&' | '?' | '#' => true,
-			_ => false,
+	// Skips the expression following a `#;`, which may itself span many lexemes.
+	fn skip_datum(&mut self, start: usize) -> Result<(), LexingError> {
+		// Step past the `;` of the opening `#;`.
+		self.index += 1;
+		let mut depth = 0;
+		loop {
+			let lexeme = match self.next() {
+				Some(lexeme) => lexeme?,
+				None => {
+					return Err(LexingError {
+						position: start,
+						kind: LexingErrorKind::UnexpectedEof,
+					})
+				}
+			};
+			match lexeme.value {
+				LexemeType::LeftParen | LexemeType::LeftCBracket => depth += 1,
+				LexemeType::RightParen | LexemeType::RightCBracket if depth == 0 => {
+					return Err(LexingError {
+						position: lexeme.index,
+						kind: LexingErrorKind::InvalidLiteral {
+							expected: String::from("an expression after `#;`"),
+							got: String::from(match lexeme.value {
+								LexemeType::RightParen => ")",
+								_ => "}",
+							}),
+						},
+					});
+				}
+				LexemeType::RightParen | LexemeType::RightCBracket => depth -= 1,
+				// The expression a reader macro applies to is skipped along with it.
+				LexemeType::Prefix(_) | LexemeType::Dispatch(_) => continue,
+				_ => {}
+			}
+			if depth == 0 {
+				return Ok(());
+			}
 		}
 	}
 
-	fn lex_raw_symbol(&mut self) -> LexemeType<'a> {
-		let symbol_start = self.index - 1;
-		LexemeType::RawSymbol(
-			&self.text[symbol_start
-				..if let Some(i) =
-					self.text[self.index - 1..].find(|x| !Self::is_valid_raw_symbol(x))
-				{
-					self.index = symbol_start + i;
-					symbol_start + i
-				} else {
-					self.index = self.text.len();
-					self.text.len()
-				}],
-		)
+	fn is_raw_string_start(rest: &str) -> bool {
+		rest.strip_prefix('r')
+			.is_some_and(|rest| rest.trim_start_matches('#').starts_with('"'))
 	}
 
-	fn lex_string_literal(&mut self) -> Result<LexemeType<'a>, LexingError> {
-		let symbol_start = self.index - 1;
+	// `#r"..."`, any number of `#` may follow the `r` and must then also follow the
+	// closing quote, so `#r#"say "hi""#` can contain quotes.
+	fn lex_raw_string_literal(&mut self, start: usize) -> Result<LexemeType<'a>, LexingError> {
+		let hashes = self.text[start + 2..]
+			.chars()
+			.take_while(|x| *x == '#')
+			.count();
+		let content_start = start + 2 + hashes + 1;
+		let terminator = format!("\"{}", "#".repeat(hashes));
+		match self.text[content_start..].find(terminator.as_str()) {
+			Some(i) => {
+				self.index = content_start + i + terminator.len();
+				Ok(LexemeType::RawStringLiteral(
+					&self.text[content_start..content_start + i],
+				))
+			}
+			None => Err(LexingError {
+				position: start,
+				kind: LexingErrorKind::UnexpectedEof,
+			}),
+		}
+	}
+
+	fn lex_multiline_string_literal(
+		&mut self,
+		start: usize,
+	) -> Result<LexemeType<'a>, LexingError> {
+		let content_start = start + 3;
+		let mut forward_iter = self.text[content_start..].char_indices();
+		while let Some((i, c)) = forward_iter.next() {
+			if c == '\\' {
+				if forward_iter.next().is_none() {
+					break;
+				}
+			} else if self.text[content_start + i..].starts_with("\"\"\"") {
+				self.index = content_start + i + 3;
+				return Ok(LexemeType::MultilineStringLiteral(
+					&self.text[content_start..content_start + i],
+				));
+			}
+		}
+		Err(LexingError {
+			position: start,
+			kind: LexingErrorKind::UnexpectedEof,
+		})
+	}
+
+	fn lex_string_literal(&mut self, symbol_start: usize) -> Result<LexemeType<'a>, LexingError> {
 		let mut forward_iter = self.text[symbol_start..].char_indices();
 		let (_, start_char) = forward_iter.next().unwrap();
 		while let Some((i, c)) = forward_iter.next() {
 			if c == '\\' {
-				forward_iter.next().unwrap();
+				// A trailing backslash escapes nothing, the literal is unterminated.
+				if forward_iter.next().is_none() {
+					break;
+				}
 			} else if c == start_char {
 				self.index = symbol_start + i + 1;
 				return Ok(LexemeType::StringLiteral(
@@ -146,7 +359,7 @@ impl<'a> Lexer<'a> {
 			}
 		}
 		Err(LexingError {
-			position: self.index,
+			position: symbol_start,
 			kind: LexingErrorKind::UnexpectedEof,
 		})
 	}
@@ -160,8 +373,9 @@ impl<'a> Iterator for Lexer<'a> {
 			return None;
 		}
 
-		while let Some(char) = self.advance_char() {
-			let index = self.index - 1;
+		loop {
+			let index = self.index;
+			let char = self.advance_char()?;
 			let resp = Some(Ok(Lexeme {
 				index,
 				value: match char {
@@ -175,31 +389,298 @@ impl<'a> Iterator for Lexer<'a> {
 							.unwrap_or(self.text.len() - self.index);
 						continue;
 					}
-					'"' | '\'' => match self.lex_string_literal() {
+					'#' if self.text[self.index..].starts_with('|') => {
+						match self.skip_block_comment(index) {
+							Ok(()) => continue,
+							Err(e) => return Some(Err(e)),
+						}
+					}
+					'#' if self.text[self.index..].starts_with(';') => {
+						match self.skip_datum(index) {
+							Ok(()) => continue,
+							Err(e) => return Some(Err(e)),
+						}
+					}
+					'"' if self.text[self.index..].starts_with("\"\"") => {
+						match self.lex_multiline_string_literal(index) {
+							Ok(val) => val,
+							Err(e) => return Some(Err(e)),
+						}
+					}
+					'#' if Self::is_raw_string_start(&self.text[self.index..]) => {
+						match self.lex_raw_string_literal(index) {
+							Ok(val) => val,
+							Err(e) => return Some(Err(e)),
+						}
+					}
+					'$' if self.text[self.index..].starts_with('"') => {
+						match self.lex_string_literal(self.index) {
+							Ok(LexemeType::StringLiteral(s)) => LexemeType::InterpolatedStringLiteral(s),
+							Ok(val) => val,
+							Err(e) => return Some(Err(e)),
+						}
+					}
+					'"' | '\'' => match self.lex_string_literal(index) {
 						Ok(val) => val,
 						Err(e) => return Some(Err(e)),
 					},
-					'0'..='9' => match self.lex_number() {
+					'-' | '+'
+						if self.text[self.index..].starts_with(|x: char| x.is_ascii_digit()) =>
+					{
+						match self.lex_number(index) {
+							Ok(val) => val,
+							Err(e) => return Some(Err(e)),
+						}
+					}
+					'0'..='9' => match self.lex_number(index) {
 						Ok(val) => val,
 						Err(e) => return Some(Err(e)),
 					},
-					' ' | '\t' | '\n' => continue,
-					x if Self::is_valid_raw_symbol(x) => self.lex_raw_symbol(),
-					invalid => {
-						return Some(Err(LexingError {
-							position: index,
-							kind: LexingErrorKind::InvalidLiteral {
-								expected: String::from(
-									"one of '(', ')', '{', '}', ';' '\"', '\'', ",
-								),
-								got: invalid.to_string(),
-							},
-						}))
-					}
+					':' => match self.lex_keyword(index) {
+						Ok(val) => val,
+						Err(e) => return Some(Err(e)),
+					},
+					x if PREFIX_CHARACTERS.contains(&x) => LexemeType::Prefix(x),
+					x if x.is_whitespace() => continue,
+					x if Self::is_symbol_start(x) => self.lex_raw_symbol(index),
+					invalid => return Some(Err(LexingError {
+						position: index,
+						kind: LexingErrorKind::InvalidLiteral {
+							expected: String::from(
+								"one of '(', ')', '{', '}', ';', '\"', '\'', ':', a number or a symbol",
+							),
+							got: invalid.to_string(),
+						},
+					})),
 				},
 			}));
 			return resp;
 		}
-		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use proptest::prelude::*;
+
+	fn lex(text: &str) -> Result<Vec<LexemeType<'_>>, LexingError> {
+		Lexer::new(text).map(|l| l.map(|l| l.value)).collect()
+	}
+
+	#[test]
+	fn symbols_allow_unicode_and_digits() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::RawSymbol("vec2"),
+				LexemeType::RawSymbol("utf8->string"),
+				LexemeType::RawSymbol("λ"),
+				LexemeType::RawSymbol("größe")
+			]),
+			lex("vec2 utf8->string λ größe")
+		);
+	}
+
+	#[test]
+	fn numbers_must_end_at_a_delimiter() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::Integer("-1"),
+				LexemeType::Float("2.5"),
+				LexemeType::RawSymbol("-"),
+				LexemeType::LeftParen,
+				LexemeType::Integer("+3"),
+				LexemeType::RightParen
+			]),
+			lex("-1 2.5 - (+3)")
+		);
+		assert_eq!(
+			Err(LexingError {
+				position: 2,
+				kind: LexingErrorKind::InvalidLiteral {
+					expected: String::from("a number"),
+					got: String::from("1+")
+				}
+			}),
+			lex("x 1+ y")
+		);
+		assert!(lex("2x").is_err());
+		assert!(lex("1.2.3").is_err());
+	}
+
+	#[test]
+	fn unicode_whitespace_and_bom_are_skipped() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::LeftParen,
+				LexemeType::RawSymbol("a"),
+				LexemeType::RawSymbol("b"),
+				LexemeType::RightParen
+			]),
+			lex("\u{FEFF}(a\r\n\u{00A0}b\u{2003})\r\n")
+		);
+	}
+
+	#[test]
+	fn trailing_backslash_is_unterminated_string() {
+		assert_eq!(
+			Err(LexingError {
+				position: 2,
+				kind: LexingErrorKind::UnexpectedEof
+			}),
+			lex("a \"b\\")
+		);
+	}
+
+	proptest! {
+		#[test]
+		fn lexing_arbitrary_text_never_panics(text in any::<String>()) {
+			let _ = lex(&text);
+		}
+
+		#[test]
+		fn lexing_lisp_like_text_never_panics(text in "[(){}\"'\\\\;#|:+\\-.0-9a-zλ \n]{0,64}") {
+			let _ = lex(&text);
+		}
+	}
+
+	#[test]
+	fn keywords_need_a_name() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::Keyword("width"),
+				LexemeType::Keyword("utf8-name?")
+			]),
+			lex(":width :utf8-name?")
+		);
+		assert!(lex(": width").is_err());
+	}
+
+	#[test]
+	fn reader_macros_lex_separately() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::Dispatch("date"),
+				LexemeType::StringLiteral("2026-01-01"),
+				LexemeType::Prefix('@'),
+				LexemeType::RawSymbol("x"),
+				LexemeType::RawSymbol("#t"),
+				LexemeType::Dispatch("set"),
+				LexemeType::LeftCBracket,
+				LexemeType::RightCBracket,
+			]),
+			lex("#date\"2026-01-01\" @x #t #set{}")
+		);
+	}
+
+	#[test]
+	fn raw_and_multiline_strings() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::RawStringLiteral("C:\\dir\\"),
+				LexemeType::RawStringLiteral("say \"hi\""),
+				LexemeType::MultilineStringLiteral("\n  a \"quote\"\n  "),
+				LexemeType::StringLiteral(""),
+				LexemeType::RawSymbol("#r"),
+			]),
+			lex("#r\"C:\\dir\\\" #r#\"say \"hi\"\"# \"\"\"\n  a \"quote\"\n  \"\"\" \"\" #r")
+		);
+		assert_eq!(
+			Err(LexingError {
+				position: 2,
+				kind: LexingErrorKind::UnexpectedEof
+			}),
+			lex("x #r#\"never \" closed")
+		);
+		assert!(lex("\"\"\" one \"\"").is_err());
+	}
+
+	#[test]
+	fn dollar_before_a_string_interpolates() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::InterpolatedStringLiteral("a {b}"),
+				LexemeType::Prefix('$'),
+				LexemeType::RawSymbol("c"),
+			]),
+			lex(r#"$"a {b}" $c"#)
+		);
+	}
+
+	#[test]
+	fn interpreter_line_is_skipped_only_at_the_start() {
+		assert_eq!(
+			Ok(vec![LexemeType::RawSymbol("a")]),
+			lex("\u{FEFF}#!/usr/bin/env lockjaw\na")
+		);
+		assert_eq!(
+			Ok(vec![
+				LexemeType::RawSymbol("a"),
+				LexemeType::RawSymbol("#!b")
+			]),
+			lex("a\n#!b")
+		);
+		let resumed: Result<Vec<_>, _> = Lexer::resume("#!b").map(|l| l.map(|l| l.value)).collect();
+		assert_eq!(Ok(vec![LexemeType::RawSymbol("#!b")]), resumed);
+	}
+
+	#[test]
+	fn block_comments_nest() {
+		assert_eq!(
+			Ok(vec![LexemeType::Integer("1"), LexemeType::Integer("2")]),
+			lex("1 #| outer #| inner |# still outer\n |# 2")
+		);
+	}
+
+	#[test]
+	fn lexeme_positions_survive_comments() {
+		let lexemes: Vec<Lexeme> = Lexer::new("#| a |# x #;(y z) w")
+			.collect::<Result<_, _>>()
+			.unwrap();
+		assert_eq!(
+			vec![8, 18],
+			lexemes.iter().map(|l| l.index).collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn unterminated_block_comment_is_eof() {
+		assert_eq!(
+			Err(LexingError {
+				position: 2,
+				kind: LexingErrorKind::UnexpectedEof
+			}),
+			lex("1 #| #| |# 2")
+		);
+	}
+
+	#[test]
+	fn datum_comment_skips_one_expression() {
+		assert_eq!(
+			Ok(vec![
+				LexemeType::LeftParen,
+				LexemeType::RawSymbol("a"),
+				LexemeType::RawSymbol("c"),
+				LexemeType::RightParen
+			]),
+			lex("(a #;{b (nested #;skipped)} c)")
+		);
+		assert_eq!(Ok(vec![LexemeType::RawSymbol("b")]), lex("#; #; 1 2 b"));
+		assert_eq!(
+			Ok(vec![LexemeType::RawSymbol("b")]),
+			lex("#; @#date\"x\" b")
+		);
+	}
+
+	#[test]
+	fn datum_comment_needs_an_expression() {
+		assert_eq!(
+			Err(LexingError {
+				position: 0,
+				kind: LexingErrorKind::UnexpectedEof
+			}),
+			lex("#; (unclosed")
+		);
+		assert!(lex("(a #;)").is_err());
 	}
 }
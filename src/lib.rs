@@ -0,0 +1,32 @@
+//! Lockjaw, a small Lisp with S-expressions `( )` and quoted Q-expressions `{ }`.
+//!
+//! [`Interpreter`] is the way in for programs embedding the language, together
+//! with the value types it takes and returns and [`Error`].
+
+mod builtins;
+mod environment;
+mod format;
+mod interpreter;
+mod prelude;
+mod printer;
+
+// The front end and evaluator the `lockjaw` binary, benchmarks and fuzz
+// targets are built on. They are not part of the embedding API.
+#[doc(hidden)]
+pub mod evaluator;
+#[doc(hidden)]
+pub mod lexer;
+#[doc(hidden)]
+pub mod numeric;
+#[doc(hidden)]
+pub mod parser;
+#[doc(hidden)]
+pub mod reader;
+#[doc(hidden)]
+pub mod readtable;
+#[doc(hidden)]
+pub mod types;
+
+pub use interpreter::Interpreter;
+pub use numeric::Numeric;
+pub use types::{Atom, Expression, Keyword, LockjawRuntimeError as Error, Value};
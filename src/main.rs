@@ -1,96 +1,210 @@
-//#![allow(dead_code)]
-mod builtins;
-mod environment;
-mod evaluator;
-mod lexer;
-mod numeric;
-mod parser;
-mod types;
-
-use std::error::Error;
-use std::path::PathBuf;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
 
 use clap::Parser;
-use lexer::LexingError;
-use rustyline::error::ReadlineError;
-use rustyline::Editor;
+use lockjaw::{Atom, Error, Expression, Interpreter};
+
+mod inspect;
+mod repl;
+#[cfg(test)]
+mod scratch;
+mod test_runner;
+
+/// Exit status when a program could not be lexed or parsed.
+const EXIT_SYNTAX_ERROR: i32 = 65;
+/// Exit status when a program failed while it was being evaluated.
+const EXIT_RUNTIME_ERROR: i32 = 70;
 
 #[derive(clap::Parser)]
 #[command(name = "lockjaw")]
 #[command(author = "Conner Bondurant")]
+#[command(after_help = "Exits with 65 on syntax errors, 70 on runtime errors \
+	and with the given status when the program calls `exit`.")]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+	#[command(subcommand)]
+	command: Option<Command>,
+	/// Program to run, `-` reads the program from stdin.
 	file: Option<PathBuf>,
+	/// Arguments for the program, bound to `argv` after the program's path.
+	#[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+	args: Vec<String>,
+	/// Evaluate an expression and print its result, may be given more than once.
+	#[arg(short, long = "eval", value_name = "EXPR")]
+	eval: Vec<String>,
+	/// Start the REPL after running the program or expressions.
 	#[arg(short, long)]
 	load_to_interpreter: bool,
+	/// Start the REPL without evaluating `~/.lockjawrc`.
+	#[arg(long)]
+	no_rc: bool,
+	/// Search DIR for libraries before `LOCKJAW_PATH` and the user library directory.
+	#[arg(short = 'I', value_name = "DIR", global = true)]
+	include: Vec<PathBuf>,
+	/// Leave out the standard prelude of list functions.
+	#[arg(long, global = true)]
+	no_prelude: bool,
+	/// Show the type of every REPL result.
+	#[arg(short, long)]
+	verbose: bool,
+	/// Only lex and parse the program and any further files, reporting every syntax error found.
+	#[arg(long)]
+	check: bool,
+	/// Print the lexemes of the program instead of running it.
+	#[arg(long)]
+	dump_tokens: bool,
+	/// Print the expression tree of the program instead of running it.
+	#[arg(long)]
+	dump_ast: bool,
+	/// Print `--dump-tokens` and `--dump-ast` output as JSON.
+	#[arg(long)]
+	json: bool,
 }
 
-fn main() {
-	let cli = Cli::parse();
+#[derive(clap::Subcommand)]
+enum Command {
+	/// Run the `deftest` forms of `*_test.lisp` files, exits with 1 if any test fails.
+	///
+	/// Each file is evaluated once, every test then runs in a copy of what the
+	/// file left behind. A top-level `exit` fails the whole file.
+	Test {
+		/// Test files, or directories to search for them, the working directory by default.
+		paths: Vec<PathBuf>,
+		/// Only run tests whose name contains NAME.
+		#[arg(long, value_name = "NAME")]
+		filter: Option<String>,
+		/// Also write the results to FILE as JUnit XML.
+		#[arg(long, value_name = "FILE")]
+		junit: Option<PathBuf>,
+	},
+}
 
-	let mut environment = evaluator::Evaluator::new();
-	if let Some(run_program) = cli.file.as_deref() {
-		let program = format!("load \"{}\"", run_program.display());
-		let lexemes: Result<Vec<lexer::Lexeme>, lexer::LexingError> =
-			lexer::Lexer::new(program.as_str()).collect();
-		match lexemes {
-			Ok(lexemes) => match parser::Parser::parse_root(lexemes.as_slice()) {
-				Ok(lj) => {
-					environment.evaluate(lj).unwrap();
-				}
-				Err(parser_err) => {
-					println!("{parser_err:?}: {:?}", parser_err.source());
-				}
-			},
-			Err(why) => println!("{why:?}"),
+// `{path arg...}`, empty when there is no program file.
+fn argv(cli: &Cli) -> Expression {
+	let path = cli.file.iter().map(|path| path.display().to_string());
+	Expression::QExpression(
+		path.chain(cli.args.iter().cloned())
+			.map(|arg| Expression::Atom(Atom::String(arg)))
+			.collect(),
+	)
+}
+
+// Where `load`, `require` and `import` look for libraries: `-I` directories,
+// then `LOCKJAW_PATH`, then `lockjaw/lib` in the user's data directory.
+fn search_path(cli: &Cli) -> Vec<PathBuf> {
+	let mut directories = cli.include.clone();
+	if let Some(path) = env::var_os("LOCKJAW_PATH") {
+		directories.extend(env::split_paths(&path).filter(|dir| !dir.as_os_str().is_empty()));
+	}
+	directories.extend(dirs_next::data_dir().map(|dir| dir.join("lockjaw").join("lib")));
+	directories
+}
+
+fn exit_code(why: &Error) -> i32 {
+	match why {
+		Error::Exit(code) => *code,
+		Error::ParserError(_) => EXIT_SYNTAX_ERROR,
+		_ => EXIT_RUNTIME_ERROR,
+	}
+}
+
+// Runs the `-e` expressions and then the program, stopping at the first error.
+// An `-e` expression is read like a REPL line, so `+ 1 2` is a call.
+fn run_batch(cli: &Cli, interpreter: &mut Interpreter) -> Result<(), Error> {
+	for expression in &cli.eval {
+		let environment = interpreter.evaluator_mut();
+		let parsed = environment.parse(expression)?;
+		let result = environment.evaluate(parsed)?;
+		if result != Expression::Null {
+			println!("{}", result.write());
 		}
-		if !cli.load_to_interpreter {
-			return;
+	}
+	match cli.file.as_deref() {
+		Some(path) if path == Path::new("-") => {
+			interpreter.eval_reader(io::stdin().lock())?;
 		}
+		Some(path) => interpreter.eval_file(path)?,
+		None => {}
 	}
+	Ok(())
+}
 
-	let mut rl = match Editor::<()>::new() {
-		Ok(rl) => rl,
-		Err(why) => {
-			println!("Error creating prompt: {}", why);
-			return;
-		}
+// `--check` and the dump modes never evaluate anything, `None` when neither was asked for.
+fn run_front_end(cli: &Cli) -> Option<i32> {
+	let stdin = PathBuf::from("-");
+	let program = cli.file.as_ref().unwrap_or(&stdin);
+	if cli.check {
+		let paths: Vec<&Path> = std::iter::once(program.as_path())
+			.chain(cli.args.iter().map(Path::new))
+			.collect();
+		return Some(if inspect::check(&paths) {
+			0
+		} else {
+			EXIT_SYNTAX_ERROR
+		});
+	}
+	if !cli.dump_tokens && !cli.dump_ast {
+		return None;
+	}
+	let format = if cli.json {
+		inspect::DumpFormat::Json
+	} else {
+		inspect::DumpFormat::Readable
 	};
+	let dumped = (!cli.dump_tokens || inspect::dump_tokens(program, format).is_ok())
+		&& (!cli.dump_ast || inspect::dump_ast(program, format).is_ok());
+	Some(if dumped { 0 } else { EXIT_SYNTAX_ERROR })
+}
 
-	loop {
-		let readline = rl.readline("lj> ");
-		match readline {
-			Ok(line) => {
-				rl.add_history_entry(line.as_str());
-				let lexemes: Result<Vec<lexer::Lexeme>, LexingError> =
-					lexer::Lexer::new(&line).collect();
-				match lexemes {
-					Ok(lexemes) => match parser::Parser::parse_root(lexemes.as_slice()) {
-						Ok(lj) => {
-							//println!("{:#?}", lj);
-							println!("{:?}", environment.evaluate(lj));
-						}
-						Err(parser_err) => {
-							println!("{parser_err:?}");
-							println!("{line}");
-						}
-					},
-					Err(why) => println!("{why:?}"),
-				}
-			}
-			Err(ReadlineError::Interrupted) => {
-				println!("CTRL+C! Closing.");
-				break;
-			}
-			Err(ReadlineError::Eof) => {
-				println!("EOF");
-				break;
-			}
-			Err(why) => {
-				println!("Unexpected Read Err: {:?}", why);
-				break;
-			}
+fn main() {
+	let cli = Cli::parse();
+	if let Some(Command::Test {
+		paths,
+		filter,
+		junit,
+	}) = &cli.command
+	{
+		process::exit(test_runner::run(
+			paths,
+			&test_runner::Options {
+				filter: filter.clone(),
+				junit: junit.clone(),
+				prelude: !cli.no_prelude,
+				search_path: search_path(&cli),
+			},
+		));
+	}
+	if let Some(code) = run_front_end(&cli) {
+		process::exit(code);
+	}
+
+	let mut interpreter = if cli.no_prelude {
+		Interpreter::without_prelude()
+	} else {
+		Interpreter::new()
+	};
+	interpreter.define("argv", argv(&cli));
+	interpreter.set_search_path(search_path(&cli));
+	if let Err(why) = run_batch(&cli, &mut interpreter) {
+		if !matches!(why, Error::Exit(_)) {
+			repl::print_error(&why);
 		}
+		process::exit(exit_code(&why));
 	}
+	if (cli.file.is_some() || !cli.eval.is_empty()) && !cli.load_to_interpreter {
+		return;
+	}
+
+	process::exit(repl::run(
+		interpreter.into_evaluator(),
+		repl::Options {
+			load_rc: !cli.no_rc,
+			verbose: cli.verbose,
+			prelude: !cli.no_prelude,
+		},
+	));
 }
 
 #[cfg(test)]
@@ -98,21 +212,18 @@ mod tests {
 
 	use std::collections::VecDeque;
 
-	use crate::evaluator;
-	use crate::lexer;
-	use crate::lexer::LexingError;
-	use crate::numeric::Numeric;
-	use crate::parser;
-	use crate::types::*;
+	use lockjaw::evaluator;
+	use lockjaw::numeric::Numeric;
+	use lockjaw::parser;
+	use lockjaw::types::*;
+
+	use crate::scratch::ScratchDir;
 
 	fn assert_program_output(commands: Vec<&str>, expected_output: Expression) {
 		let mut environment = evaluator::Evaluator::new();
 		let mut result: Expression = Expression::SExpression(VecDeque::new());
 		for command in commands {
-			let lexemes: Vec<lexer::Lexeme> = lexer::Lexer::new(&command)
-				.collect::<Result<Vec<lexer::Lexeme>, LexingError>>()
-				.unwrap();
-			let parse = parser::Parser::parse_root(lexemes.as_slice()).unwrap();
+			let parse = environment.parse(command).unwrap();
 			result = environment.evaluate(parse).unwrap();
 		}
 		assert_eq!(expected_output, result);
@@ -142,6 +253,14 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn negative_literals_lex_as_numbers() {
+		assert_program_output(
+			vec!["+ -3 4 -0.5"],
+			Expression::Atom(Atom::Number(Numeric::Float(0.5))),
+		);
+	}
+
 	#[test]
 	fn minus_negates() {
 		assert_program_output(
@@ -273,6 +392,25 @@ mod tests {
 		)
 	}
 
+	#[test]
+	fn comments_remove_cond_branches() {
+		assert_program_output(
+			vec![
+				"def {sign}
+				(fun {n}
+					{cond
+						#| negative numbers are
+						   #| not |# handled yet |#
+						#;{(lt? n 0)
+							-1}
+						{(zero? n) 0}
+						{else 1}})",
+				"sign -5",
+			],
+			Expression::Atom(Atom::Number(Numeric::Int(1))),
+		)
+	}
+
 	#[test]
 	fn boolean_expressions_confirm() {
 		assert_program_output(vec!["and? #t #t"], Expression::Atom(Atom::Bool(true)));
@@ -289,6 +427,514 @@ mod tests {
 		assert_program_output(vec!["xor? #f #f"], Expression::Atom(Atom::Bool(false)));
 	}
 
+	#[test]
+	fn keywords_evaluate_to_themselves() {
+		assert_program_output(
+			vec![":unbound-name"],
+			Expression::Atom(Atom::Keyword(Keyword::new("unbound-name"))),
+		);
+		assert_program_output(vec!["eq? :a :a"], Expression::Atom(Atom::Bool(true)));
+		assert_program_output(vec!["eq? :a :b"], Expression::Atom(Atom::Bool(false)));
+	}
+
+	#[test]
+	fn format_fills_in_directives() {
+		assert_program_output(
+			vec![r#"format "{name}: {:>6.2} {:?}" 3.14159 :name "pi" "str""#],
+			Expression::Atom(Atom::String(String::from(r#"pi:   3.14 "str""#))),
+		);
+	}
+
+	#[test]
+	fn interpolated_strings_evaluate_embedded_expressions() {
+		assert_program_output(
+			vec![
+				"def {items} 3",
+				r#"$"{items} items cost {* items 1.5:.2}, {{total}}: {'x'}""#,
+			],
+			Expression::Atom(Atom::String(String::from("3 items cost 4.50, {total}: x"))),
+		);
+	}
+
+	#[test]
+	fn interpolated_strings_ignore_a_rebound_format() {
+		assert_program_output(
+			vec![
+				"def {format} (fun {t} {t})",
+				"def {#format} (fun {t} {t})",
+				r#"$"{+ 1 2} and {3}""#,
+			],
+			Expression::Atom(Atom::String(String::from("3 and 3"))),
+		);
+	}
+
+	#[test]
+	fn exit_stops_evaluation_with_a_status() {
+		let mut environment = evaluator::Evaluator::new();
+		let why = environment
+			.load_reader("(def {x} 1) (exit 3) (def {x} 2)".as_bytes())
+			.unwrap_err();
+		assert!(matches!(why, LockjawRuntimeError::Exit(3)));
+		assert_eq!(3, super::exit_code(&why));
+		let parse = environment.parse("x").unwrap();
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(1))),
+			environment.evaluate(parse).unwrap()
+		);
+
+		let parse = environment.parse("exit 1.5").unwrap();
+		assert!(matches!(
+			environment.evaluate(parse),
+			Err(LockjawRuntimeError::InvalidArguments(_))
+		));
+	}
+
+	#[test]
+	fn error_raises_its_message() {
+		let mut environment = evaluator::Evaluator::new();
+		let parse = environment.parse("error \"out of range\"").unwrap();
+		let why = environment.evaluate(parse).unwrap_err();
+		assert!(matches!(&why, LockjawRuntimeError::Raised(message) if message == "out of range"));
+		assert_eq!(super::EXIT_RUNTIME_ERROR, super::exit_code(&why));
+		assert_program_output(vec!["integer? 2"], Expression::Atom(Atom::Bool(true)));
+		assert_program_output(vec!["integer? 2.0"], Expression::Atom(Atom::Bool(false)));
+	}
+
+	#[test]
+	fn syntax_and_runtime_errors_exit_differently() {
+		let mut environment = evaluator::Evaluator::new();
+		let syntax = environment.load_reader("(+ 1 2".as_bytes()).unwrap_err();
+		let runtime = environment.load_reader("(+ 1 {2})".as_bytes()).unwrap_err();
+		assert_eq!(super::EXIT_SYNTAX_ERROR, super::exit_code(&syntax));
+		assert_eq!(super::EXIT_RUNTIME_ERROR, super::exit_code(&runtime));
+	}
+
+	#[test]
+	fn script_arguments_are_bound_to_argv() {
+		use clap::Parser;
+		let cli = super::Cli::parse_from(["lockjaw", "tool.lisp", "foo", "--flag", "-e"]);
+		let mut environment = evaluator::Evaluator::new();
+		environment.bind("argv", super::argv(&cli));
+		let parse = environment.parse("cdr argv").unwrap();
+		assert_eq!(
+			Expression::QExpression(VecDeque::from([
+				Expression::Atom(Atom::String(String::from("foo"))),
+				Expression::Atom(Atom::String(String::from("--flag"))),
+				Expression::Atom(Atom::String(String::from("-e"))),
+			])),
+			environment.evaluate(parse).unwrap()
+		);
+		assert!(cli.eval.is_empty());
+	}
+
+	#[test]
+	fn runtime_errors_display_readably() {
+		let mut environment = evaluator::Evaluator::new();
+		let mut error = |command: &str| {
+			let parse = environment.parse(command).unwrap();
+			environment.evaluate(parse).unwrap_err().to_string()
+		};
+		assert_eq!(
+			"wrong number of arguments: Car only takes one argument.",
+			error("car {1} {2}")
+		);
+		assert_eq!("unbound symbol: nothing-here", error("nothing-here"));
+		assert_eq!(
+			"parse error: no reader macro named `#x` at index 0",
+			environment
+				.load_reader("#x(1)".as_bytes())
+				.unwrap_err()
+				.to_string()
+		);
+	}
+
+	#[test]
+	fn raw_and_multiline_strings_evaluate_to_strings() {
+		assert_program_output(
+			vec![r#"def {re} #r"\d+\.\d+""#, "re"],
+			Expression::Atom(Atom::String(String::from(r"\d+\.\d+"))),
+		);
+		assert_program_output(
+			vec![
+				"def {doc} \"\"\"\n    usage:\n      lockjaw \\u{2192} run\n    \"\"\"",
+				"doc",
+			],
+			Expression::Atom(Atom::String(String::from("usage:\n  lockjaw → run"))),
+		);
+	}
+
+	#[test]
+	fn keywords_convert_to_strings_and_symbols() {
+		assert_program_output(
+			vec!["keyword->string :width"],
+			Expression::Atom(Atom::String(String::from("width"))),
+		);
+		assert_program_output(
+			vec!["eq? (string->keyword \"width\") :width"],
+			Expression::Atom(Atom::Bool(true)),
+		);
+		assert_program_output(
+			vec!["keyword->symbol :width"],
+			Expression::Atom(Atom::Symbol(String::from("width"))),
+		);
+		assert_program_output(
+			vec!["eq? (symbol->keyword (car {width})) :width"],
+			Expression::Atom(Atom::Bool(true)),
+		);
+		assert_program_output(vec!["keyword? :width"], Expression::Atom(Atom::Bool(true)));
+		let mut environment = evaluator::Evaluator::new();
+		for name in ["\"\"", "\"a b\"", "\"a:b\""] {
+			let parse = environment
+				.parse(&format!("string->keyword {name}"))
+				.unwrap();
+			assert!(matches!(
+				environment.evaluate(parse),
+				Err(LockjawRuntimeError::InvalidArguments(_))
+			));
+		}
+		assert_program_output(
+			vec!["keyword? \"width\""],
+			Expression::Atom(Atom::Bool(false)),
+		);
+	}
+
+	#[test]
+	fn load_reader_evaluates_forms_before_reading_the_next() {
+		let mut environment = evaluator::Evaluator::new();
+		let program = "(def {x} 3)\n(def {y}\n 4)\n(+ x";
+		assert!(environment
+			.load_reader(std::io::Cursor::new(program))
+			.is_err());
+		let sum = parser::Parser::parse_from_text("+ x y").unwrap();
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(7))),
+			environment.evaluate(sum).unwrap()
+		);
+	}
+
+	#[test]
+	fn load_resolves_paths_from_the_loading_file() {
+		let root = ScratchDir::new(
+			"relative",
+			&[
+				("lib/main.lj", "(load \"util/x.lj\")"),
+				("lib/util/x.lj", "(load \"y.lj\")\n(def {x} (+ y 1))"),
+				("lib/util/y.lj", "(def {y} 2)"),
+			],
+		);
+		let mut environment = evaluator::Evaluator::new();
+		environment.load_file(&root.join("lib/main.lj")).unwrap();
+		assert_eq!(
+			Some(&Value::Variable(Box::new(Expression::Atom(Atom::Number(
+				Numeric::Int(3)
+			))))),
+			environment.lookup("x")
+		);
+	}
+
+	#[test]
+	fn require_loads_each_file_once() {
+		let root = ScratchDir::new(
+			"once",
+			&[
+				(
+					"main.lj",
+					"(def {n} 0)\n(require \"count.lj\")\n(require \"./count.lj\")",
+				),
+				("count.lj", "(def {n} (+ n 1))"),
+			],
+		);
+		let mut environment = evaluator::Evaluator::new();
+		environment.load_file(&root.join("main.lj")).unwrap();
+		assert_eq!(
+			Some(&Value::Variable(Box::new(Expression::Atom(Atom::Number(
+				Numeric::Int(1)
+			))))),
+			environment.lookup("n")
+		);
+	}
+
+	#[test]
+	fn modules_requiring_one_file_each_get_its_definitions() {
+		let root = ScratchDir::new(
+			"shared",
+			&[
+				(
+					"main.lisp",
+					"(import \"a.lisp\") (import \"b.lisp\") (require \"util\")",
+				),
+				(
+					"a.lisp",
+					"(module {fa}) (require \"util\") (def {fa} (fun {x} {double x}))",
+				),
+				(
+					"b.lisp",
+					"(module {fb}) (require \"util\") (def {fb} (fun {x} {double x}))",
+				),
+				("util.lisp", "(def {double} (fun {x} {* x 2}))"),
+			],
+		);
+		let mut environment = evaluator::Evaluator::new();
+		environment.load_file(&root.join("main.lisp")).unwrap();
+		for call in ["a/fa 1", "b/fb 1", "double 1"] {
+			assert_eq!(
+				Expression::Atom(Atom::Number(Numeric::Int(2))),
+				evaluate_in(&mut environment, call).unwrap()
+			);
+		}
+	}
+
+	#[test]
+	fn load_cycles_report_the_chain() {
+		let root = ScratchDir::new(
+			"cycle",
+			&[
+				("main.lj", "(require \"a.lj\")"),
+				("a.lj", "(require \"b.lj\")"),
+				("b.lj", "(load \"a.lj\")"),
+			],
+		);
+		let mut environment = evaluator::Evaluator::new();
+		let chain = match environment.load_file(&root.join("main.lj")) {
+			Err(LockjawRuntimeError::LoadCycle(chain)) => chain,
+			other => panic!("expected a load cycle, got {other:?}"),
+		};
+		let names: Vec<_> = chain.iter().map(|p| p.file_name().unwrap()).collect();
+		assert_eq!(names, ["a.lj", "b.lj", "a.lj"]);
+		// The failed loads leave nothing behind, so the file can be loaded again.
+		std::fs::write(root.join("b.lj"), "(def {b} 1)").unwrap();
+		environment.load_file(&root.join("main.lj")).unwrap();
+	}
+
+	fn evaluate_in(
+		environment: &mut evaluator::Evaluator,
+		command: &str,
+	) -> Result<Expression, LockjawRuntimeError> {
+		let parse = environment.parse(command)?;
+		environment.evaluate(parse)
+	}
+
+	#[test]
+	fn imports_bind_only_exports() {
+		let root = ScratchDir::new(
+			"modules",
+			&[
+				(
+					"lib/strings.lj",
+					"(module {twice})\n(def {helper} (fun {x} {* x 2}))\n(def {twice} (fun {x} {helper x}))",
+				),
+				(
+					"main.lj",
+					"(import \"lib/strings.lj\")\n(import \"lib/strings.lj\" :as \"s\")\n(import \"lib/strings.lj\" :only {twice})",
+				),
+			],
+		);
+		let mut environment = evaluator::Evaluator::new();
+		environment.load_file(&root.join("main.lj")).unwrap();
+		for call in ["strings/twice 4", "s/twice 4", "twice 4"] {
+			assert_eq!(
+				Expression::Atom(Atom::Number(Numeric::Int(8))),
+				evaluate_in(&mut environment, call).unwrap()
+			);
+		}
+		assert!(matches!(
+			evaluate_in(&mut environment, "helper 4"),
+			Err(LockjawRuntimeError::UnboundExpression(name)) if name == "helper"
+		));
+		// Definitions in the importer don't leak into the module either.
+		evaluate_in(&mut environment, "def {helper} (fun {x} {x})").unwrap();
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(8))),
+			evaluate_in(&mut environment, "twice 4").unwrap()
+		);
+	}
+
+	#[test]
+	fn modules_are_cached_by_path() {
+		let root = ScratchDir::new("module-cache", &[("m.lj", "(module {v})\n(def {v} 1)")]);
+		let mut environment = evaluator::Evaluator::new();
+		let path = root.join("m.lj").display().to_string();
+		evaluate_in(&mut environment, &format!("import \"{path}\" :as \"a\"")).unwrap();
+		std::fs::write(root.join("m.lj"), "(module {v})\n(def {v} 2)").unwrap();
+		evaluate_in(&mut environment, &format!("import \"{path}\" :as \"b\"")).unwrap();
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(1))),
+			evaluate_in(&mut environment, "b/v").unwrap()
+		);
+	}
+
+	#[test]
+	fn imports_check_what_modules_export() {
+		let root = ScratchDir::new(
+			"module-errors",
+			&[
+				("m.lj", "(module {v w})\n(def {v} 1)"),
+				("plain.lj", "(def {v} 1)"),
+			],
+		);
+		let mut environment = evaluator::Evaluator::new();
+		for import in [
+			"import \"m.lj\" :only {x}",
+			"import \"m.lj\"",
+			"import \"plain.lj\"",
+			"import \"m.lj\" :prefix \"p\"",
+		] {
+			let import = import.replacen('"', &format!("\"{}/", root.display()), 1);
+			assert!(matches!(
+				evaluate_in(&mut environment, &import),
+				Err(LockjawRuntimeError::InvalidArguments(_))
+			));
+		}
+	}
+
+	#[test]
+	fn bare_names_resolve_through_the_search_path() {
+		let root = ScratchDir::new(
+			"search-path",
+			&[
+				("main.lisp", "(require \"json-utils\")\n(load \"sub/x.lj\")"),
+				("first/json-utils.lisp", "(def {source} 1)"),
+				("second/json-utils.lisp", "(def {source} 2)"),
+				("second/sub/x.lj", "(def {x} 3)"),
+			],
+		);
+		let mut environment = evaluator::Evaluator::new();
+		environment.set_search_path(vec![root.join("first"), root.join("second")]);
+		environment.load_file(&root.join("main.lisp")).unwrap();
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(1))),
+			evaluate_in(&mut environment, "source").unwrap()
+		);
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(3))),
+			evaluate_in(&mut environment, "x").unwrap()
+		);
+	}
+
+	#[test]
+	fn missing_files_list_the_directories_searched() {
+		let root = ScratchDir::new("not-found", &[("lib/a.lisp", "")]);
+		let mut environment = evaluator::Evaluator::new();
+		environment.set_search_path(vec![root.join("lib"), root.join("more")]);
+		match evaluate_in(&mut environment, "require \"b\"") {
+			Err(LockjawRuntimeError::FileNotFound { name, searched }) => {
+				assert_eq!(name, "b");
+				assert_eq!(searched.len(), 3);
+			}
+			other => panic!("expected a missing file, got {other:?}"),
+		}
+		// Paths starting with `./` are only looked for next to the loading file.
+		assert!(matches!(
+			evaluate_in(&mut environment, "load \"./a\""),
+			Err(LockjawRuntimeError::FileNotFound { searched, .. }) if searched.len() == 1
+		));
+		evaluate_in(&mut environment, "load \"a\"").unwrap();
+	}
+
+	#[test]
+	fn integer_overflow_is_an_error() {
+		let mut environment = evaluator::Evaluator::new();
+		for (command, operator) in [
+			("+ 9223372036854775807 1", "+"),
+			("- -9223372036854775807 2", "-"),
+			("* 4611686018427387904 2", "*"),
+			("- (- -9223372036854775807 1)", "-"),
+		] {
+			match evaluate_in(&mut environment, command) {
+				Err(LockjawRuntimeError::IntegerOverflow(name)) => assert_eq!(name, operator),
+				other => panic!("expected {command} to overflow, got {other:?}"),
+			}
+		}
+		assert_program_output(
+			vec!["+ 9223372036854775807 1.0"],
+			Expression::Atom(Atom::Number(Numeric::Float(9223372036854775808.0))),
+		);
+	}
+
+	#[test]
+	fn runaway_recursion_is_an_error() {
+		let mut environment = evaluator::Evaluator::new();
+		let parse = parser::Parser::parse_from_text("def {f} (fun {x} {f x})").unwrap();
+		environment.evaluate(parse).unwrap();
+		let parse = parser::Parser::parse_from_text("f 1").unwrap();
+		assert!(matches!(
+			environment.evaluate(parse),
+			Err(LockjawRuntimeError::RecursionLimitExceeded)
+		));
+		// The failed call must not leave its local frame behind.
+		let parse = parser::Parser::parse_from_text("def {x} 2").unwrap();
+		environment.evaluate(parse).unwrap();
+		let parse = parser::Parser::parse_from_text("+ x 1").unwrap();
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(3))),
+			environment.evaluate(parse).unwrap()
+		);
+	}
+
+	#[test]
+	fn lisp_reader_macros_expand_at_read_time() {
+		assert_program_output(
+			vec![
+				"set-reader-macro \"first\" (fun {d} {car d})",
+				"set-reader-prefix \"^\" (fun {d} {* d d})",
+				"+ #first{5 6} ^3",
+			],
+			Expression::Atom(Atom::Number(Numeric::Int(14))),
+		);
+		// The datum is passed as read, `(undefined 1)` is never evaluated as a call.
+		let mut environment = evaluator::Evaluator::new();
+		let define = environment
+			.parse("set-reader-macro \"id\" (fun {d} {eval {d}})")
+			.unwrap();
+		environment.evaluate(define).unwrap();
+		assert_eq!(
+			Ok(Expression::SExpression(VecDeque::from([
+				Expression::SExpression(VecDeque::from([
+					Expression::Atom(Atom::Symbol(String::from("undefined"))),
+					Expression::Atom(Atom::Number(Numeric::Int(1))),
+				]))
+			]))),
+			environment.parse("#id(undefined 1)")
+		);
+	}
+
+	fn date_literal(mut args: VecDeque<Expression>) -> Result<Expression, LockjawRuntimeError> {
+		let date = args.pop_front().unwrap().get_atom()?.get_as_string()?;
+		let parts = date
+			.split('-')
+			.map(|part| {
+				part.parse()
+					.map(|n| Expression::Atom(Atom::Number(Numeric::Int(n))))
+					.map_err(|_| LockjawRuntimeError::InvalidArguments(date.clone()))
+			})
+			.collect::<Result<_, _>>()?;
+		Ok(Expression::QExpression(parts))
+	}
+
+	#[test]
+	fn rust_reader_macros_expand_at_read_time() {
+		let mut environment = evaluator::Evaluator::new();
+		environment
+			.read_table_mut()
+			.set_dispatch("date", Value::Builtin(date_literal))
+			.unwrap();
+		let parse = environment.parse("car (cdr #date\"2026-01-01\")").unwrap();
+		assert_eq!(
+			Expression::Atom(Atom::Number(Numeric::Int(1))),
+			environment.evaluate(parse).unwrap()
+		);
+		match environment.parse("#date\"2026-x\"") {
+			Err(parser::ParsingError::ReaderMacroFailure { index: 0, message }) => {
+				assert_eq!(message, "invalid arguments: 2026-x")
+			}
+			other => panic!("expected a reader macro failure, got {other:?}"),
+		}
+		assert!(matches!(
+			environment.parse("#time\"12:00\""),
+			Err(parser::ParsingError::UnknownReaderMacro { index: 0, .. })
+		));
+	}
+
 	#[test]
 	fn weird_string_literals_pass() {
 		assert_program_output(
@@ -1,7 +1,4 @@
-use std::{
-	fmt::Display,
-	ops::{Add, Div, Mul, Neg, Sub},
-};
+use std::{fmt::Display, ops::Div};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Numeric {
@@ -22,48 +19,43 @@ impl PartialOrd for Numeric {
 
 impl Display for Numeric {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		match self {
-			Numeric::Float(float) => write!(f, "{}", float),
-			Numeric::Int(i) => write!(f, "{}", i),
-		}
+		crate::printer::fmt_number(self, f)
 	}
 }
 
-impl Add for Numeric {
-	type Output = Self;
-
-	fn add(self, rhs: Self) -> Self::Output {
+// Integer operations that overflow give `None`, mixing in a float makes the result a float.
+impl Numeric {
+	pub fn checked_add(self, rhs: Self) -> Option<Self> {
 		match (self, rhs) {
-			(Numeric::Float(f1), Numeric::Float(f2)) => Numeric::Float(f1 + f2),
-			(Numeric::Float(f), Numeric::Int(i)) => Numeric::Float(f + i as f64),
-			(Numeric::Int(i), Numeric::Float(f)) => Numeric::Float(i as f64 + f),
-			(Numeric::Int(i1), Numeric::Int(i2)) => Numeric::Int(i1 + i2),
+			(Numeric::Float(f1), Numeric::Float(f2)) => Some(Numeric::Float(f1 + f2)),
+			(Numeric::Float(f), Numeric::Int(i)) => Some(Numeric::Float(f + i as f64)),
+			(Numeric::Int(i), Numeric::Float(f)) => Some(Numeric::Float(i as f64 + f)),
+			(Numeric::Int(i1), Numeric::Int(i2)) => i1.checked_add(i2).map(Numeric::Int),
 		}
 	}
-}
 
-impl Sub for Numeric {
-	type Output = Self;
-
-	fn sub(self, rhs: Self) -> Self::Output {
+	pub fn checked_sub(self, rhs: Self) -> Option<Self> {
 		match (self, rhs) {
-			(Numeric::Float(f1), Numeric::Float(f2)) => Numeric::Float(f1 - f2),
-			(Numeric::Float(f), Numeric::Int(i)) => Numeric::Float(f - i as f64),
-			(Numeric::Int(i), Numeric::Float(f)) => Numeric::Float(i as f64 - f),
-			(Numeric::Int(i1), Numeric::Int(i2)) => Numeric::Int(i1 - i2),
+			(Numeric::Float(f1), Numeric::Float(f2)) => Some(Numeric::Float(f1 - f2)),
+			(Numeric::Float(f), Numeric::Int(i)) => Some(Numeric::Float(f - i as f64)),
+			(Numeric::Int(i), Numeric::Float(f)) => Some(Numeric::Float(i as f64 - f)),
+			(Numeric::Int(i1), Numeric::Int(i2)) => i1.checked_sub(i2).map(Numeric::Int),
 		}
 	}
-}
 
-impl Mul for Numeric {
-	type Output = Self;
-
-	fn mul(self, rhs: Self) -> Self::Output {
+	pub fn checked_mul(self, rhs: Self) -> Option<Self> {
 		match (self, rhs) {
-			(Numeric::Float(f1), Numeric::Float(f2)) => Numeric::Float(f1 * f2),
-			(Numeric::Float(f), Numeric::Int(i)) => Numeric::Float(f * i as f64),
-			(Numeric::Int(i), Numeric::Float(f)) => Numeric::Float(i as f64 * f),
-			(Numeric::Int(i1), Numeric::Int(i2)) => Numeric::Int(i1 * i2),
+			(Numeric::Float(f1), Numeric::Float(f2)) => Some(Numeric::Float(f1 * f2)),
+			(Numeric::Float(f), Numeric::Int(i)) => Some(Numeric::Float(f * i as f64)),
+			(Numeric::Int(i), Numeric::Float(f)) => Some(Numeric::Float(i as f64 * f)),
+			(Numeric::Int(i1), Numeric::Int(i2)) => i1.checked_mul(i2).map(Numeric::Int),
+		}
+	}
+
+	pub fn checked_neg(self) -> Option<Self> {
+		match self {
+			Numeric::Float(f) => Some(Numeric::Float(-f)),
+			Numeric::Int(i) => i.checked_neg().map(Numeric::Int),
 		}
 	}
 }
@@ -81,17 +73,6 @@ impl Div for Numeric {
 	}
 }
 
-impl Neg for Numeric {
-	type Output = Self;
-
-	fn neg(self) -> Self::Output {
-		match self {
-			Numeric::Float(f) => Numeric::Float(-f),
-			Numeric::Int(i) => Numeric::Int(-i),
-		}
-	}
-}
-
 impl From<i64> for Numeric {
 	fn from(i: i64) -> Self {
 		Numeric::Int(i)
@@ -10,16 +10,21 @@ use std::{
 use crate::{
 	lexer::{Lexeme, LexemeType},
 	numeric::Numeric,
+	readtable::{MacroName, ReaderMacros},
 	types::*,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParsingError {
 	InvalidLiteral { index: usize },
-	InvalidStringLiteral { code: char },
+	MaxDepthExceeded { index: usize, max_depth: usize },
+	UnknownReaderMacro { index: usize, name: String },
+	ReaderMacroFailure { index: usize, message: String },
+	InvalidStringLiteral { index: usize, escape: String },
 	LexingError(LexingError),
 	IntParseFailure(<i64 as FromStr>::Err),
 	FloatParseFailure(<f64 as FromStr>::Err),
+	ReadFailure(std::io::ErrorKind),
 	UnexpectedEof,
 }
 
@@ -27,15 +32,44 @@ impl Display for ParsingError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			ParsingError::InvalidLiteral { index } => write!(f, "invalid literal at index {index}"),
-			ParsingError::InvalidStringLiteral { code } => {
-				write!(f, "invalid character in string literal: {code}")
+			ParsingError::MaxDepthExceeded { index, max_depth } => write!(
+				f,
+				"expression at index {index} is nested deeper than the limit of {max_depth}"
+			),
+			ParsingError::UnknownReaderMacro { index, name } => {
+				write!(f, "no reader macro named `{name}` at index {index}")
 			}
-			ParsingError::LexingError(_) => write!(f, "failure at lexing stage"),
+			ParsingError::ReaderMacroFailure { index, message } => {
+				write!(f, "reader macro at index {index} failed: {message}")
+			}
+			ParsingError::InvalidStringLiteral { index, escape } => {
+				write!(
+					f,
+					"invalid escape `{escape}` in string literal at index {index}"
+				)
+			}
+			ParsingError::LexingError(e) => write!(f, "{e}"),
 			ParsingError::UnexpectedEof => {
-				write!(f, "reached end of file before end of string literal")
+				write!(f, "unexpected end of input")
 			}
 			ParsingError::IntParseFailure(_) => write!(f, "failed to parse integer literal"),
 			ParsingError::FloatParseFailure(_) => write!(f, "failed to parse float literal"),
+			ParsingError::ReadFailure(kind) => write!(f, "failed to read input: {kind}"),
+		}
+	}
+}
+
+impl ParsingError {
+	/// Byte offset into the source the error points at, if it points anywhere.
+	pub fn index(&self) -> Option<usize> {
+		match self {
+			ParsingError::InvalidLiteral { index }
+			| ParsingError::MaxDepthExceeded { index, .. }
+			| ParsingError::UnknownReaderMacro { index, .. }
+			| ParsingError::ReaderMacroFailure { index, .. }
+			| ParsingError::InvalidStringLiteral { index, .. } => Some(*index),
+			ParsingError::LexingError(e) => Some(e.position()),
+			_ => None,
 		}
 	}
 }
@@ -68,104 +102,599 @@ impl Error for ParsingError {
 	}
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct Parser {
-	ast: Expression,
+/// Nesting depth `Parser` allows unless told otherwise, deep enough for any
+/// hand written program while keeping the recursion well inside the stack.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Head of the call `$"..."` reads as. The evaluator always resolves it to the
+/// `format` builtin, whatever `format` is bound to, and it writes and reads
+/// back as an ordinary symbol.
+pub const FORMAT_HEAD: &str = "#format";
+
+/// Builds expressions from a slice of lexemes, walking it once with a cursor.
+pub struct Parser<'l, 'a, 'm> {
+	lexemes: &'l [Lexeme<'a>],
+	cursor: usize,
+	depth: usize,
+	max_depth: usize,
+	macros: Option<&'m mut dyn ReaderMacros>,
 }
 
-impl Parser {
-	pub fn lexeme_len(expr: &Expression) -> usize {
-		match expr {
-			// Open paren, Operator, <expr list> Close Paren.
-			Expression::SExpression(exprlist) => {
-				2 + exprlist.iter().map(Self::lexeme_len).sum::<usize>()
-			}
-			Expression::QExpression(exprlist) => {
-				2 + exprlist.iter().map(Self::lexeme_len).sum::<usize>()
-			}
-			Expression::Atom(_) => 1,
-			Expression::Null => 2,
+impl<'l, 'a, 'm> Parser<'l, 'a, 'm> {
+	pub fn new(lexemes: &'l [Lexeme<'a>]) -> Self {
+		Parser {
+			lexemes,
+			cursor: 0,
+			depth: 0,
+			max_depth: DEFAULT_MAX_DEPTH,
+			macros: None,
 		}
 	}
 
-	pub fn parse_string_literal(s: &str) -> Result<String, ParsingError> {
-		let mut iter = s.chars();
+	pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+		self.max_depth = max_depth;
+		self
+	}
+
+	/// Without reader macros any `@datum` or `#name datum` is an error.
+	pub fn with_reader_macros(mut self, macros: &'m mut dyn ReaderMacros) -> Self {
+		self.macros = Some(macros);
+		self
+	}
+
+	/// True once every lexeme has been consumed.
+	pub fn is_finished(&self) -> bool {
+		self.cursor >= self.lexemes.len()
+	}
+
+	/// Processes the escapes in the contents of a string literal, `offset` is
+	/// where `s` starts in the source and is used to position errors.
+	pub fn parse_string_literal(s: &str, offset: usize) -> Result<String, ParsingError> {
+		let mut iter = s.char_indices().peekable();
 		let mut escaped = String::with_capacity(s.len());
-		while let Some(c) = iter.next() {
-			escaped.push(match c {
-				'\\' => match iter.next() {
-					Some(escape) => match escape {
-						't' => '\t',
-						'n' => '\n',
-						'r' => '\r',
-						'0' => '\0',
-						'\\' => '\\',
-						'"' => '"',
-						'\'' => '\'',
-						_ => return Err(ParsingError::InvalidStringLiteral { code: escape }),
-					},
-					None => return Err(ParsingError::UnexpectedEof),
-				},
-				_ => c,
-			})
+		while let Some((i, c)) = iter.next() {
+			if c != '\\' {
+				escaped.push(c);
+				continue;
+			}
+			let invalid = |end: usize| ParsingError::InvalidStringLiteral {
+				index: offset + i,
+				escape: s[i..end.min(s.len())].to_string(),
+			};
+			let Some((j, escape)) = iter.next() else {
+				return Err(ParsingError::UnexpectedEof);
+			};
+			escaped.push(match escape {
+				't' => '\t',
+				'n' => '\n',
+				'r' => '\r',
+				'0' => '\0',
+				'\\' => '\\',
+				'"' => '"',
+				'\'' => '\'',
+				// `\xHH`, exactly two hex digits naming a code point up to U+00FF.
+				'x' => {
+					let end = j + 3;
+					let digits = s.get(j + 1..end).ok_or_else(|| invalid(s.len()))?;
+					// `from_str_radix` would also take a sign.
+					if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+						return Err(invalid(end));
+					}
+					let code = u8::from_str_radix(digits, 16).map_err(|_| invalid(end))?;
+					iter.nth(1);
+					char::from(code)
+				}
+				// `\u{H...}`, one to six hex digits naming any Unicode scalar value.
+				'u' => {
+					let close = s[j..].find('}').map(|k| j + k);
+					let digits = match (s[j + 1..].starts_with('{'), close) {
+						(true, Some(close)) => &s[j + 2..close],
+						_ => return Err(invalid(j + 2)),
+					};
+					let end = close.unwrap() + 1;
+					if digits.is_empty()
+						|| digits.len() > 6
+						|| !digits.bytes().all(|b| b.is_ascii_hexdigit())
+					{
+						return Err(invalid(end));
+					}
+					let code = u32::from_str_radix(digits, 16)
+						.ok()
+						.and_then(char::from_u32)
+						.ok_or_else(|| invalid(end))?;
+					while iter.next_if(|(k, _)| *k < end).is_some() {}
+					code
+				}
+				_ => return Err(invalid(j + escape.len_utf8())),
+			});
 		}
 		Ok(escaped)
 	}
 
+	/// Processes a `"""` literal: a newline right after the opening quotes and a
+	/// last line holding only whitespace are dropped, then the indentation
+	/// shared by every non-blank line is stripped before escapes are processed.
+	pub fn parse_multiline_string_literal(s: &str, offset: usize) -> Result<String, ParsingError> {
+		// Report bad escapes against the source as written.
+		Self::parse_string_literal(s, offset)?;
+
+		let s = s
+			.strip_prefix("\r\n")
+			.or_else(|| s.strip_prefix('\n'))
+			.unwrap_or(s);
+		let s = match s.rsplit_once('\n') {
+			Some((body, last)) if last.trim().is_empty() => body.strip_suffix('\r').unwrap_or(body),
+			_ => s,
+		};
+		let indent = s
+			.lines()
+			.filter(|line| !line.trim().is_empty())
+			.map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+			.min()
+			.unwrap_or(0);
+		let dedented: Vec<&str> = s
+			.split('\n')
+			.map(|line| {
+				line.get(indent..)
+					.unwrap_or(line.trim_start_matches([' ', '\t']))
+			})
+			.collect();
+		Self::parse_string_literal(&dedented.join("\n"), offset)
+	}
+
+	/// Turns the contents of `$"..."` into a call to [`FORMAT_HEAD`], so
+	/// rebinding `format` doesn't change what it means. Each `{expr}`
+	/// becomes an argument, several expressions in one pair of braces are
+	/// applied like an SExpression and `{expr:spec}` passes `spec` on to
+	/// `format`. `{{` and `}}` are literal braces.
+	fn parse_interpolated_string(
+		&mut self,
+		s: &str,
+		offset: usize,
+	) -> Result<Expression, ParsingError> {
+		let mut template = String::new();
+		let mut expressions =
+			VecDeque::from([Expression::Atom(Atom::Symbol(String::from(FORMAT_HEAD)))]);
+		let mut literal_start = 0;
+		let flush = |template: &mut String, start: usize, end: usize| {
+			let literal = Self::parse_string_literal(&s[start..end], offset + start)?;
+			template.push_str(&literal.replace('{', "{{").replace('}', "}}"));
+			Ok::<(), ParsingError>(())
+		};
+		let mut i = 0;
+		while let Some(c) = s[i..].chars().next() {
+			match c {
+				// Escapes are skipped whole, `\u{...}` has braces of its own.
+				'\\' => {
+					i += 1 + s[i + 1..].chars().next().map_or(0, char::len_utf8);
+					if s[..i].ends_with('u') && s[i..].starts_with('{') {
+						i += s[i..].find('}').map_or(0, |close| close + 1);
+					}
+				}
+				'{' | '}' if s[i + 1..].starts_with(c) => {
+					flush(&mut template, literal_start, i)?;
+					template.push_str(&s[i..i + 2]);
+					i += 2;
+					literal_start = i;
+				}
+				'{' => {
+					flush(&mut template, literal_start, i)?;
+					let (expression, spec, end) =
+						self.parse_interpolation(&s[i + 1..], offset + i + 1)?;
+					template.push('{');
+					if let Some(spec) = spec {
+						template.push(':');
+						template.push_str(spec);
+					}
+					template.push('}');
+					expressions.push_back(expression);
+					i += 1 + end;
+					literal_start = i;
+				}
+				'}' => return Err(ParsingError::InvalidLiteral { index: offset + i }),
+				c => i += c.len_utf8(),
+			}
+		}
+		flush(&mut template, literal_start, s.len())?;
+		expressions.insert(1, Expression::Atom(Atom::String(template)));
+		Ok(Expression::SExpression(expressions))
+	}
+
+	// Parses one `{...}` of an interpolated string, `s` starts after the `{`.
+	// Returns the expression, its format spec and how far into `s` the closing `}` ends.
+	fn parse_interpolation<'s>(
+		&mut self,
+		s: &'s str,
+		offset: usize,
+	) -> Result<(Expression, Option<&'s str>, usize), ParsingError> {
+		let unterminated = ParsingError::InvalidLiteral { index: offset - 1 };
+		let mut lexer = lexer::Lexer::resume(s);
+		let mut lexemes = Vec::new();
+		let mut depth = 0usize;
+		let (end, spec) = loop {
+			// A `:` directly after the expression starts its spec, which runs to the `}`.
+			let position = lexer.position();
+			if depth == 0 && !lexemes.is_empty() && s[position..].starts_with(':') {
+				let close = s[position..]
+					.find('}')
+					.ok_or_else(|| unterminated.clone())?;
+				break (
+					position + close + 1,
+					Some(&s[position + 1..position + close]),
+				);
+			}
+			let lexeme = match lexer.next() {
+				Some(lexeme) => lexeme.map_err(|e| e.offset_by(offset))?,
+				None => return Err(unterminated),
+			};
+			match lexeme.value {
+				LexemeType::RightCBracket if depth == 0 => break (lexer.position(), None),
+				LexemeType::LeftParen | LexemeType::LeftCBracket => depth += 1,
+				LexemeType::RightParen | LexemeType::RightCBracket => {
+					depth = depth.saturating_sub(1)
+				}
+				_ => {}
+			}
+			lexemes.push(Lexeme {
+				index: lexeme.index + offset,
+				value: lexeme.value,
+			});
+		};
+
+		let mut parser = Parser {
+			lexemes: &lexemes,
+			cursor: 0,
+			depth: self.depth,
+			max_depth: self.max_depth,
+			macros: match &mut self.macros {
+				Some(macros) => Some(&mut **macros),
+				None => None,
+			},
+		};
+		let mut parsed = match parser.parse_all()? {
+			Expression::SExpression(parsed) => parsed,
+			_ => unreachable!("parse_all always returns an SExpression"),
+		};
+		let expression = match parsed.len() {
+			0 => return Err(ParsingError::InvalidLiteral { index: offset - 1 }),
+			1 => parsed.pop_front().unwrap(),
+			_ => Expression::SExpression(parsed),
+		};
+		Ok((expression, spec, end))
+	}
+
 	pub fn parse_from_text(s: &str) -> Result<Expression, ParsingError> {
 		let lexemes: Result<Vec<lexer::Lexeme>, LexingError> = lexer::Lexer::new(s).collect();
-		Self::parse_root(lexemes?.as_slice())
+		Parser::parse_root(lexemes?.as_slice())
+	}
+
+	/// Parses the first expression in `lexemes`, ignoring anything after it.
+	pub fn parse(lexemes: &'l [Lexeme<'a>]) -> Result<Expression, ParsingError> {
+		Parser::new(lexemes).parse_expression()
+	}
+
+	/// Parses every expression in `lexemes` into a single SExpression.
+	pub fn parse_root(lexemes: &'l [Lexeme<'a>]) -> Result<Expression, ParsingError> {
+		Parser::new(lexemes).parse_all()
 	}
 
-	pub fn parse(lexemes: &[Lexeme]) -> Result<Expression, ParsingError> {
-		match lexemes[0].value {
+	pub fn parse_all(&mut self) -> Result<Expression, ParsingError> {
+		let mut expressions = VecDeque::new();
+		while !self.is_finished() {
+			expressions.push_back(self.parse_expression()?);
+		}
+		Ok(Expression::SExpression(expressions))
+	}
+
+	/// Parses the expression under the cursor and moves past it.
+	pub fn parse_expression(&mut self) -> Result<Expression, ParsingError> {
+		let lexeme = *self
+			.lexemes
+			.get(self.cursor)
+			.ok_or(ParsingError::UnexpectedEof)?;
+		self.cursor += 1;
+		match lexeme.value {
 			LexemeType::LeftParen => {
-				let mut exprlist = VecDeque::new();
-				let mut current_lexeme = 1;
-				while current_lexeme < lexemes.len()
-					&& LexemeType::RightParen != lexemes[current_lexeme].value
-				{
-					let expression = Self::parse(&lexemes[current_lexeme..])?;
-					current_lexeme += Self::lexeme_len(&expression);
-					exprlist.push_back(expression);
+				let exprlist = self.parse_list(lexeme.index, LexemeType::RightParen)?;
+				if exprlist.is_empty() {
+					Ok(Expression::Null)
+				} else {
+					Ok(Expression::SExpression(exprlist))
 				}
-				Ok(Expression::SExpression(exprlist))
 			}
-			LexemeType::LeftCBracket => {
-				let mut exprlist = VecDeque::new();
-				let mut current_lexeme = 1;
-				while current_lexeme < lexemes.len()
-					&& LexemeType::RightCBracket != lexemes[current_lexeme].value
-				{
-					let expression = Self::parse(&lexemes[current_lexeme..])?;
-					current_lexeme += Self::lexeme_len(&expression);
-					exprlist.push_back(expression);
-				}
-				Ok(Expression::QExpression(exprlist))
+			LexemeType::LeftCBracket => Ok(Expression::QExpression(
+				self.parse_list(lexeme.index, LexemeType::RightCBracket)?,
+			)),
+			LexemeType::Prefix(c) => self.parse_reader_macro(lexeme.index, MacroName::Prefix(c)),
+			LexemeType::Dispatch(name) => {
+				self.parse_reader_macro(lexeme.index, MacroName::Dispatch(name))
 			}
 			term => Ok(Expression::Atom(match term {
 				LexemeType::Integer(value) => Atom::Number(Numeric::Int(value.parse()?)),
-				LexemeType::Float(value) => Atom::Number(Numeric::Float(value.parse()?)),
-				LexemeType::StringLiteral(str) => Atom::String(Self::parse_string_literal(str)?),
+				LexemeType::Float(value) => match value.parse::<f64>()? {
+					// Too many digits to fit, there is no literal for infinity.
+					float if float.is_infinite() => {
+						return Err(ParsingError::InvalidLiteral {
+							index: lexeme.index,
+						})
+					}
+					float => Atom::Number(Numeric::Float(float)),
+				},
+				LexemeType::StringLiteral(str) => {
+					Atom::String(Self::parse_string_literal(str, lexeme.index + 1)?)
+				}
+				LexemeType::RawStringLiteral(str) => Atom::String(str.to_string()),
+				LexemeType::MultilineStringLiteral(str) => {
+					Atom::String(Self::parse_multiline_string_literal(str, lexeme.index + 3)?)
+				}
+				LexemeType::InterpolatedStringLiteral(str) => {
+					return self.parse_interpolated_string(str, lexeme.index + 2)
+				}
+				LexemeType::RawSymbol("#t") => Atom::Bool(true),
+				LexemeType::RawSymbol("#f") => Atom::Bool(false),
 				LexemeType::RawSymbol(symb) => Atom::Symbol(symb.to_string()),
+				LexemeType::Keyword(name) => Atom::Keyword(Keyword::new(name)),
 				_ => {
 					return Err(ParsingError::InvalidLiteral {
-						index: lexemes[0].index,
+						index: lexeme.index,
 					})
 				}
 			})),
 		}
 	}
 
-	pub fn parse_root(lexemes: &[Lexeme]) -> Result<Expression, ParsingError> {
-		let mut expressions = VecDeque::new();
-		let mut lexemes_consumed = 0;
-		while lexemes_consumed < lexemes.len() {
-			let expression = Self::parse(&lexemes[lexemes_consumed..])?;
-			lexemes_consumed += Self::lexeme_len(&expression);
-			expressions.push_back(expression);
+	fn parse_reader_macro(
+		&mut self,
+		index: usize,
+		name: MacroName,
+	) -> Result<Expression, ParsingError> {
+		let datum = self.parse_expression()?;
+		let expansion = self
+			.macros
+			.as_mut()
+			.and_then(|macros| macros.expand(name, datum))
+			.ok_or_else(|| ParsingError::UnknownReaderMacro {
+				index,
+				name: name.to_string(),
+			})?;
+		expansion.map_err(|e| ParsingError::ReaderMacroFailure {
+			index,
+			message: e.to_string(),
+		})
+	}
+
+	// Parses expressions up to and including `close`, the opening lexeme has already been consumed.
+	fn parse_list(
+		&mut self,
+		index: usize,
+		close: LexemeType,
+	) -> Result<VecDeque<Expression>, ParsingError> {
+		if self.depth >= self.max_depth {
+			return Err(ParsingError::MaxDepthExceeded {
+				index,
+				max_depth: self.max_depth,
+			});
 		}
+		self.depth += 1;
+		let mut exprlist = VecDeque::new();
+		loop {
+			match self.lexemes.get(self.cursor) {
+				Some(lexeme) if lexeme.value == close => {
+					self.cursor += 1;
+					break;
+				}
+				Some(_) => exprlist.push_back(self.parse_expression()?),
+				None => return Err(ParsingError::UnexpectedEof),
+			}
+		}
+		self.depth -= 1;
+		Ok(exprlist)
+	}
+}
 
-		Ok(Expression::SExpression(expressions))
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn lex(text: &str) -> Vec<Lexeme<'_>> {
+		lexer::Lexer::new(text)
+			.collect::<Result<_, LexingError>>()
+			.unwrap()
+	}
+
+	#[test]
+	fn unclosed_brackets_are_eof() {
+		assert_eq!(
+			Err(ParsingError::UnexpectedEof),
+			Parser::parse_from_text("(+ 1 {2 3}")
+		);
+		assert_eq!(
+			Err(ParsingError::UnexpectedEof),
+			Parser::parse_from_text("{")
+		);
+	}
+
+	#[test]
+	fn stray_closing_bracket_is_invalid() {
+		assert_eq!(
+			Err(ParsingError::InvalidLiteral { index: 4 }),
+			Parser::parse_from_text("(a) ) b")
+		);
+	}
+
+	#[test]
+	fn out_of_range_floats_are_invalid() {
+		let text = format!("x {}.0", "9".repeat(400));
+		assert_eq!(
+			Err(ParsingError::InvalidLiteral { index: 2 }),
+			Parser::parse_from_text(&text)
+		);
+	}
+
+	fn string(s: &str) -> Result<Expression, ParsingError> {
+		Ok(Expression::SExpression(VecDeque::from([Expression::Atom(
+			Atom::String(s.to_string()),
+		)])))
+	}
+
+	#[test]
+	fn unicode_and_hex_escapes() {
+		assert_eq!(
+			string("😀 é A\u{10FFFF}"),
+			Parser::parse_from_text(r#""\u{1F600} \u{e9} \x41\u{10FFFF}""#)
+		);
+	}
+
+	#[test]
+	fn invalid_escapes_point_at_the_backslash() {
+		for (text, index, escape) in [
+			(r#"x "ab\q""#, 5, r"\q"),
+			(r#""\x4""#, 1, r#"\x4""#),
+			(r#""\xZZ""#, 1, r"\xZZ"),
+			(r#""é\u{D800}""#, 3, r"\u{D800}"),
+			(r#""\u{1234567}""#, 1, r"\u{1234567}"),
+			(r#""\u{}""#, 1, r"\u{}"),
+			(r#""\u1F600""#, 1, r"\u1"),
+			(r#""\x+1""#, 1, r"\x+1"),
+			(r#""\u{+41}""#, 1, r"\u{+41}"),
+		] {
+			let escape = escape.trim_end_matches('"').to_string();
+			assert_eq!(
+				Err(ParsingError::InvalidStringLiteral { index, escape }),
+				Parser::parse_from_text(text),
+				"{text}"
+			);
+		}
+	}
+
+	#[test]
+	fn raw_strings_ignore_escapes() {
+		assert_eq!(
+			string(r"C:\Users\n\d+"),
+			Parser::parse_from_text(r#"#r"C:\Users\n\d+""#)
+		);
+		assert_eq!(
+			string(r#"He said "hi""#),
+			Parser::parse_from_text(r##"#r#"He said "hi""#"##)
+		);
+	}
+
+	#[test]
+	fn multiline_strings_strip_indentation() {
+		let text = "\"\"\"\n\t\t  first\n\t\t    second \\\"\\u{21}\n\n\t\t  third\n\t\t\"\"\"";
+		assert_eq!(
+			string("first\n  second \"!\n\nthird"),
+			Parser::parse_from_text(text)
+		);
+		assert_eq!(
+			string("inline"),
+			Parser::parse_from_text("\"\"\"inline\"\"\"")
+		);
+	}
+
+	#[test]
+	fn multiline_escape_errors_use_source_positions() {
+		assert_eq!(
+			Err(ParsingError::InvalidStringLiteral {
+				index: 10,
+				escape: String::from(r"\q")
+			}),
+			Parser::parse_from_text("\"\"\"\n    a \\q\n  \"\"\"")
+		);
+	}
+
+	fn format_call(template: &str, arguments: Vec<Expression>) -> Expression {
+		let mut call = VecDeque::from([
+			Expression::Atom(Atom::Symbol(String::from(FORMAT_HEAD))),
+			Expression::Atom(Atom::String(template.to_string())),
+		]);
+		call.extend(arguments);
+		Expression::SExpression(VecDeque::from([Expression::SExpression(call)]))
+	}
+
+	fn symbol(s: &str) -> Expression {
+		Expression::Atom(Atom::Symbol(s.to_string()))
+	}
+
+	#[test]
+	fn interpolated_strings_become_format_calls() {
+		assert_eq!(
+			Ok(format_call(
+				"x={} sum={:>4} {{ok}} {{{{",
+				vec![
+					symbol("x"),
+					Expression::SExpression(VecDeque::from([
+						symbol("+"),
+						symbol("x"),
+						Expression::QExpression(VecDeque::from([symbol("y")])),
+					])),
+				]
+			)),
+			Parser::parse_from_text(r#"$"x={x} sum={+ x {y}:>4} {{ok}} \u{7b}\u{7B}""#)
+		);
+		assert_eq!(
+			Ok(format_call(
+				"{}",
+				vec![Expression::Atom(Atom::Keyword(Keyword::new("k")))]
+			)),
+			Parser::parse_from_text(r#"$"{:k}""#)
+		);
+	}
+
+	#[test]
+	fn malformed_interpolations_are_invalid() {
+		for (text, index) in [(r#"$"a {b""#, 4), (r#"$"a { }""#, 4), (r#"$"a } b""#, 4)] {
+			assert_eq!(
+				Err(ParsingError::InvalidLiteral { index }),
+				Parser::parse_from_text(text),
+				"{text}"
+			);
+		}
+	}
+
+	#[test]
+	fn cursor_stops_after_each_expression() {
+		let lexemes = lex("(a {b}) c");
+		let mut parser = Parser::new(&lexemes);
+		assert!(matches!(
+			parser.parse_expression(),
+			Ok(Expression::SExpression(_))
+		));
+		assert_eq!(
+			Ok(Expression::Atom(Atom::Symbol(String::from("c")))),
+			parser.parse_expression()
+		);
+		assert!(parser.is_finished());
+	}
+
+	#[test]
+	fn nesting_up_to_the_limit_parses() {
+		let text = format!(
+			"{}x{}",
+			"{".repeat(DEFAULT_MAX_DEPTH),
+			"}".repeat(DEFAULT_MAX_DEPTH)
+		);
+		assert!(Parser::parse_from_text(&text).is_ok());
+	}
+
+	#[test]
+	fn nesting_past_the_limit_is_an_error() {
+		let text = format!("{}{}", "(".repeat(100_000), ")".repeat(100_000));
+		assert_eq!(
+			Err(ParsingError::MaxDepthExceeded {
+				index: DEFAULT_MAX_DEPTH,
+				max_depth: DEFAULT_MAX_DEPTH
+			}),
+			Parser::parse_from_text(&text)
+		);
+
+		let lexemes = lex("(a (b (c)))");
+		assert_eq!(
+			Err(ParsingError::MaxDepthExceeded {
+				index: 6,
+				max_depth: 2
+			}),
+			Parser::new(&lexemes).with_max_depth(2).parse_all()
+		);
 	}
 }
@@ -0,0 +1,71 @@
+; The standard prelude, evaluated by `Evaluator::new` once the builtins are registered.
+; Lists are indexed from 0, `nth` and `last` give {} when there is no such element.
+; An index or count that is negative or not an integer is an error.
+
+(def {not} (fun {x} {
+	cond
+		{x #f}
+		{else #t}
+}))
+
+(def {length} (fun {xs} {
+	cond
+		{(null? xs) 0}
+		{else (+ 1 (length (cdr xs)))}
+}))
+
+(def {foldl} (fun {f acc xs} {
+	cond
+		{(null? xs) acc}
+		{else (foldl f (f acc (car xs)) (cdr xs))}
+}))
+
+(def {map} (fun {f xs} {
+	cond
+		{(null? xs) {}}
+		{else (join (list (f (car xs))) (map f (cdr xs)))}
+}))
+
+(def {filter} (fun {p xs} {
+	cond
+		{(null? xs) {}}
+		{(p (car xs)) (join (list (car xs)) (filter p (cdr xs)))}
+		{else (filter p (cdr xs))}
+}))
+
+(def {index?} (fun {n} {
+	cond
+		{(integer? n) (not (lt? n 0))}
+		{else #f}
+}))
+
+(def {reverse} (fun {xs} {
+	foldl (fun {acc x} {join (list x) acc}) {} xs
+}))
+
+(def {nth} (fun {n xs} {
+	cond
+		{(not (index? n)) (error "nth takes a non-negative integer index")}
+		{(zero? n) (car xs)}
+		{else (nth (- n 1) (cdr xs))}
+}))
+
+(def {last} (fun {xs} {
+	cond
+		{(null? (cdr xs)) (car xs)}
+		{else (last (cdr xs))}
+}))
+
+(def {take} (fun {n xs} {
+	cond
+		{(not (index? n)) (error "take takes a non-negative integer count")}
+		{(or? (zero? n) (null? xs)) {}}
+		{else (join (list (car xs)) (take (- n 1) (cdr xs)))}
+}))
+
+(def {drop} (fun {n xs} {
+	cond
+		{(not (index? n)) (error "drop takes a non-negative integer count")}
+		{(or? (zero? n) (null? xs)) xs}
+		{else (drop (- n 1) (cdr xs))}
+}))
@@ -0,0 +1,118 @@
+//! The standard prelude, list functions written in Lockjaw itself.
+
+/// Source of the prelude, compiled into the binary.
+pub const PRELUDE: &str = include_str!("prelude.lisp");
+
+#[cfg(test)]
+mod tests {
+	use crate::evaluator::Evaluator;
+	use crate::numeric::Numeric;
+	use crate::types::*;
+
+	fn evaluate(command: &str) -> Expression {
+		let mut environment = Evaluator::new();
+		let parse = environment.parse(command).unwrap();
+		environment.evaluate(parse).unwrap()
+	}
+
+	fn error(command: &str) -> String {
+		let mut environment = Evaluator::new();
+		let parse = environment.parse(command).unwrap();
+		environment.evaluate(parse).unwrap_err().to_string()
+	}
+
+	fn int(i: i64) -> Expression {
+		Expression::Atom(Atom::Number(Numeric::Int(i)))
+	}
+
+	fn ints<const N: usize>(items: [i64; N]) -> Expression {
+		Expression::QExpression(items.into_iter().map(int).collect())
+	}
+
+	#[test]
+	fn not_negates() {
+		assert_eq!(evaluate("not #t"), Expression::Atom(Atom::Bool(false)));
+		assert_eq!(
+			evaluate("not (zero? 1)"),
+			Expression::Atom(Atom::Bool(true))
+		);
+	}
+
+	#[test]
+	fn length_counts_items() {
+		assert_eq!(evaluate("length {}"), int(0));
+		assert_eq!(evaluate("length {1 {2 3} 4}"), int(3));
+	}
+
+	#[test]
+	fn foldl_folds_from_the_left() {
+		assert_eq!(evaluate("foldl - 10 {1 2 3}"), int(4));
+		assert_eq!(evaluate("foldl + 0 {}"), int(0));
+	}
+
+	#[test]
+	fn map_applies_to_every_item() {
+		assert_eq!(evaluate("map (fun {x} {* x x}) {1 2 3}"), ints([1, 4, 9]));
+		assert_eq!(evaluate("map (fun {x} {x}) {}"), ints([]));
+	}
+
+	#[test]
+	fn filter_keeps_matching_items() {
+		assert_eq!(evaluate("filter (fun {x} {gt? x 1}) {1 2 3}"), ints([2, 3]));
+	}
+
+	#[test]
+	fn reverse_reverses() {
+		assert_eq!(evaluate("reverse {1 2 3}"), ints([3, 2, 1]));
+		assert_eq!(evaluate("reverse {}"), ints([]));
+	}
+
+	#[test]
+	fn nth_and_last_index_lists() {
+		assert_eq!(evaluate("nth 0 {1 2 3}"), int(1));
+		assert_eq!(evaluate("nth 2 {1 2 3}"), int(3));
+		assert_eq!(evaluate("nth 5 {1 2 3}"), ints([]));
+		assert_eq!(evaluate("last {1 2 3}"), int(3));
+		assert_eq!(evaluate("last {}"), ints([]));
+	}
+
+	#[test]
+	fn indexes_must_be_non_negative_integers() {
+		for command in ["nth -1 {1 2 3}", "nth 1.5 {1 2 3}", "nth \"a\" {1}"] {
+			assert_eq!(error(command), "nth takes a non-negative integer index");
+		}
+		for command in ["take -1 {1 2}", "take 1.5 {1 2}", "take -1 {}"] {
+			assert_eq!(error(command), "take takes a non-negative integer count");
+		}
+		for command in ["drop -1 {1 2}", "drop 0.5 {1 2}"] {
+			assert_eq!(error(command), "drop takes a non-negative integer count");
+		}
+	}
+
+	#[test]
+	fn take_and_drop_split_lists() {
+		assert_eq!(evaluate("take 2 {1 2 3}"), ints([1, 2]));
+		assert_eq!(evaluate("take 5 {1 2}"), ints([1, 2]));
+		assert_eq!(evaluate("drop 2 {1 2 3}"), ints([3]));
+		assert_eq!(evaluate("drop 5 {1 2}"), ints([]));
+	}
+
+	#[test]
+	fn rebinding_names_does_not_break_the_prelude() {
+		let mut environment = Evaluator::new();
+		let parse = environment.parse("def {foldl} 1").unwrap();
+		environment.evaluate(parse).unwrap();
+		let parse = environment.parse("reverse {1 2}").unwrap();
+		assert_eq!(environment.evaluate(parse).unwrap(), ints([2, 1]));
+	}
+
+	#[test]
+	fn the_prelude_can_be_left_out() {
+		let mut environment = Evaluator::without_prelude();
+		let parse = environment.parse("map").unwrap();
+		assert!(matches!(
+			environment.evaluate(parse),
+			Err(LockjawRuntimeError::UnboundExpression(name)) if name == "map"
+		));
+	}
+}
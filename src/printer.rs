@@ -0,0 +1,275 @@
+use crate::numeric::Numeric;
+use crate::types::*;
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+	// Machine readable output, `Parser::parse_from_text` reads it back to an equal value.
+	// The exceptions are NaN and the infinities, which have no literal syntax,
+	// and values such as functions that only exist at run time.
+	Write,
+	// Human readable output, strings are printed raw.
+	Display,
+}
+
+pub struct Printer<'a> {
+	expression: &'a Expression,
+	style: Style,
+}
+
+impl<'a> Printer<'a> {
+	pub fn new(expression: &'a Expression, style: Style) -> Self {
+		Printer { expression, style }
+	}
+}
+
+impl Display for Printer<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+		fmt_expression(self.expression, self.style, f)
+	}
+}
+
+pub struct AtomPrinter<'a> {
+	atom: &'a Atom,
+	style: Style,
+}
+
+impl<'a> AtomPrinter<'a> {
+	pub fn new(atom: &'a Atom, style: Style) -> Self {
+		AtomPrinter { atom, style }
+	}
+}
+
+impl Display for AtomPrinter<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+		fmt_atom(self.atom, self.style, f)
+	}
+}
+
+fn fmt_list(
+	open: &str,
+	close: &str,
+	expressions: &std::collections::VecDeque<Expression>,
+	style: Style,
+	f: &mut Formatter<'_>,
+) -> Result {
+	write!(f, "{open}")?;
+	for (i, expr) in expressions.iter().enumerate() {
+		if i > 0 {
+			write!(f, " ")?;
+		}
+		fmt_expression(expr, style, f)?;
+	}
+	write!(f, "{close}")
+}
+
+fn fmt_expression(expression: &Expression, style: Style, f: &mut Formatter<'_>) -> Result {
+	match expression {
+		Expression::Atom(atom) => fmt_atom(atom, style, f),
+		Expression::SExpression(v) => fmt_list("(", ")", v, style, f),
+		Expression::QExpression(v) => fmt_list("{", "}", v, style, f),
+		Expression::Null => write!(f, "()"),
+	}
+}
+
+fn fmt_atom(atom: &Atom, style: Style, f: &mut Formatter<'_>) -> Result {
+	match atom {
+		Atom::Number(num) => fmt_number(num, f),
+		Atom::Symbol(symb) => write!(f, "{symb}"),
+		Atom::Keyword(keyword) => write!(f, "{keyword}"),
+		Atom::Bool(true) => write!(f, "#t"),
+		Atom::Bool(false) => write!(f, "#f"),
+		Atom::String(s) => match style {
+			Style::Write => fmt_string_literal(s, f),
+			Style::Display => write!(f, "{s}"),
+		},
+		Atom::Value(value) => fmt_value(value, style, f),
+	}
+}
+
+pub fn fmt_value(value: &Value, style: Style, f: &mut Formatter<'_>) -> Result {
+	match value {
+		Value::Variable(e) => fmt_expression(e, style, f),
+		Value::UserDef(func) => {
+			write!(f, "#<function ")?;
+			fmt_list("{", "}", &func.args, style, f)?;
+			write!(f, ">")
+		}
+		Value::Eval
+		| Value::Def
+		| Value::Cond
+		| Value::Load
+		| Value::Require
+		| Value::Module
+		| Value::Import
+		| Value::DefTest
+		| Value::AssertError
+		| Value::SetReaderMacro
+		| Value::SetReaderPrefix
+		| Value::Builtin(_) => write!(f, "#<builtin>"),
+	}
+}
+
+pub fn fmt_number(num: &Numeric, f: &mut Formatter<'_>) -> Result {
+	match num {
+		Numeric::Int(i) => write!(f, "{i}"),
+		// Rust never uses exponent notation for `{}`, so the only thing needed to keep
+		// floats readable as floats is making sure a decimal point is present.
+		// Non-finite values have no literal syntax and print as Rust does, so
+		// `NaN`, `inf` and `-inf` read back as symbols.
+		Numeric::Float(float) if float.is_finite() && float.fract() == 0.0 => {
+			write!(f, "{float}.0")
+		}
+		Numeric::Float(float) => write!(f, "{float}"),
+	}
+}
+
+fn fmt_string_literal(s: &str, f: &mut Formatter<'_>) -> Result {
+	write!(f, "\"")?;
+	for c in s.chars() {
+		match c {
+			'\\' => write!(f, "\\\\")?,
+			'"' => write!(f, "\\\"")?,
+			'\n' => write!(f, "\\n")?,
+			'\t' => write!(f, "\\t")?,
+			'\r' => write!(f, "\\r")?,
+			'\0' => write!(f, "\\0")?,
+			c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+			c => write!(f, "{c}")?,
+		}
+	}
+	write!(f, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::VecDeque;
+
+	use proptest::prelude::*;
+
+	use crate::numeric::Numeric;
+	use crate::parser::Parser;
+	use crate::types::*;
+
+	fn arb_atom() -> impl Strategy<Value = Atom> {
+		prop_oneof![
+			any::<i64>().prop_map(|i| Atom::Number(Numeric::Int(i))),
+			(prop::num::f64::NORMAL | prop::num::f64::SUBNORMAL | prop::num::f64::ZERO)
+				.prop_map(|f| Atom::Number(Numeric::Float(f))),
+			any::<String>().prop_map(Atom::String),
+			"[a-zA-Zλ_*/<>=!?&][a-zA-Z0-9λ_*/<>=!?&+-]{0,8}".prop_map(Atom::Symbol),
+			"[a-z][a-z0-9-]{0,8}".prop_map(|name| Atom::Keyword(Keyword::new(&name))),
+			any::<bool>().prop_map(Atom::Bool),
+		]
+	}
+
+	fn arb_expression() -> impl Strategy<Value = Expression> {
+		let leaf = prop_oneof![
+			9 => arb_atom().prop_map(Expression::Atom),
+			1 => Just(Expression::Null),
+		];
+		leaf.prop_recursive(6, 64, 8, |inner| {
+			prop_oneof![
+				// An empty SExpression reads back as Null.
+				prop::collection::vec_deque(inner.clone(), 1..8).prop_map(Expression::SExpression),
+				prop::collection::vec_deque(inner, 0..8).prop_map(Expression::QExpression),
+			]
+		})
+	}
+
+	proptest! {
+		#[test]
+		fn written_expressions_round_trip(expression in arb_expression()) {
+			assert_round_trips(expression);
+		}
+	}
+
+	fn assert_round_trips(expression: Expression) {
+		let written = expression.write().to_string();
+		let reparsed = Parser::parse_from_text(&written).unwrap();
+		assert_eq!(
+			Expression::SExpression(VecDeque::from([expression])),
+			reparsed,
+			"{written}"
+		);
+	}
+
+	#[test]
+	fn numbers_round_trip() {
+		assert_round_trips(Expression::Atom(Atom::Number(Numeric::Int(3))));
+		assert_round_trips(Expression::Atom(Atom::Number(Numeric::Int(-42))));
+		assert_round_trips(Expression::Atom(Atom::Number(Numeric::Float(3.0))));
+		assert_round_trips(Expression::Atom(Atom::Number(Numeric::Float(-0.125))));
+		assert_round_trips(Expression::Atom(Atom::Number(Numeric::Float(1e300))));
+		assert_round_trips(Expression::Atom(Atom::Number(Numeric::Float(0.1 + 0.2))));
+	}
+
+	#[test]
+	fn non_finite_floats_read_back_as_symbols() {
+		for (float, written) in [
+			(f64::NAN, "NaN"),
+			(f64::INFINITY, "inf"),
+			(f64::NEG_INFINITY, "-inf"),
+		] {
+			let expression = Expression::Atom(Atom::Number(Numeric::Float(float)));
+			assert_eq!(written, expression.write().to_string());
+			assert_eq!(
+				Parser::parse_from_text(written),
+				Ok(Expression::SExpression(VecDeque::from([Expression::Atom(
+					Atom::Symbol(String::from(written))
+				)])))
+			);
+		}
+	}
+
+	#[test]
+	fn floats_keep_decimal_point() {
+		let three = Expression::Atom(Atom::Number(Numeric::Float(3.0)));
+		assert_eq!("3.0", three.write().to_string());
+		assert_eq!("3.0", three.display().to_string());
+	}
+
+	#[test]
+	fn strings_round_trip() {
+		assert_round_trips(Expression::Atom(Atom::String(String::from(
+			"quote \" backslash \\ newline \n tab \t nul \0 emoji 😇",
+		))));
+	}
+
+	#[test]
+	fn display_prints_raw_strings() {
+		let s = Expression::Atom(Atom::String(String::from("a \"b\"\n")));
+		assert_eq!("a \"b\"\n", s.display().to_string());
+		assert_eq!("\"a \\\"b\\\"\\n\"", s.write().to_string());
+	}
+
+	#[test]
+	fn interpolated_strings_round_trip() {
+		let Expression::SExpression(mut items) =
+			Parser::parse_from_text(r#"$"{x} and {+ 1 2:>4} {{}}""#).unwrap()
+		else {
+			unreachable!()
+		};
+		let call = items.pop_front().unwrap();
+		assert_eq!(
+			r#"(#format "{} and {:>4} {{}}" x (+ 1 2))"#,
+			call.write().to_string()
+		);
+		assert_round_trips(call);
+	}
+
+	#[test]
+	fn nested_expressions_round_trip() {
+		assert_round_trips(Expression::SExpression(VecDeque::from([
+			Expression::Atom(Atom::Symbol(String::from("+"))),
+			Expression::Atom(Atom::Number(Numeric::Int(1))),
+			Expression::QExpression(VecDeque::from([
+				Expression::Atom(Atom::Bool(true)),
+				Expression::Atom(Atom::Bool(false)),
+				Expression::Atom(Atom::Keyword(Keyword::new("key"))),
+				Expression::Null,
+				Expression::QExpression(VecDeque::new()),
+			])),
+		])));
+	}
+}
@@ -0,0 +1,266 @@
+use crate::lexer::{Lexeme, LexemeType, Lexer, LexingError, LexingErrorKind};
+use crate::parser::{Parser, ParsingError};
+use crate::readtable::ReaderMacros;
+use crate::types::*;
+use std::io::BufRead;
+
+/// Pulls top level expressions one at a time out of any `BufRead`.
+///
+/// Input is read a line at a time until the buffered text holds a complete
+/// expression, only that expression is parsed and the text it came from is
+/// dropped, so memory use is bounded by the largest single expression.
+pub struct Reader<R: BufRead> {
+	input: R,
+	buffer: String,
+	// Bytes of `buffer` already known to hold complete lexemes of the current expression.
+	scanned: usize,
+	depth: usize,
+	// Set after a reader macro, which is not complete without the expression following it.
+	awaiting_datum: bool,
+	// Bytes drained from the front of `buffer`, so errors point into the whole input.
+	consumed: usize,
+	eof: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+	pub fn new(input: R) -> Self {
+		Reader {
+			input,
+			buffer: String::new(),
+			scanned: 0,
+			depth: 0,
+			awaiting_datum: false,
+			consumed: 0,
+			eof: false,
+		}
+	}
+
+	// Drops all buffered text, used when what remains can't form an expression.
+	fn discard_buffer(&mut self) {
+		self.consumed += self.buffer.len();
+		self.buffer.clear();
+		self.scanned = 0;
+		self.depth = 0;
+		self.awaiting_datum = false;
+	}
+
+	// Only text at the very start of the input can hold an interpreter line.
+	fn lexer<'t>(&self, text: &'t str, offset: usize) -> Lexer<'t> {
+		if offset == 0 {
+			Lexer::new(text)
+		} else {
+			Lexer::resume(text)
+		}
+	}
+
+	// Scans forward from where the last call stopped, returning the end of the
+	// current expression if the buffer holds all of it.
+	fn scan(&mut self) -> Result<Option<usize>, LexingError> {
+		let start = self.scanned;
+		let mut lexer = self.lexer(&self.buffer[start..], self.consumed + start);
+		loop {
+			let lexeme = match lexer.next() {
+				Some(Ok(lexeme)) => lexeme,
+				// Strings and comments may be continued by the next line.
+				Some(Err(e)) if *e.kind() == LexingErrorKind::UnexpectedEof && !self.eof => {
+					return Ok(None)
+				}
+				Some(Err(e)) => return Err(e.offset_by(self.consumed + start)),
+				None => {
+					if self.depth == 0 && !self.awaiting_datum {
+						// Nothing but whitespace and comments, none of it is needed.
+						self.discard_buffer();
+					}
+					return Ok(None);
+				}
+			};
+			self.awaiting_datum = false;
+			match lexeme.value {
+				LexemeType::LeftParen | LexemeType::LeftCBracket => self.depth += 1,
+				LexemeType::RightParen | LexemeType::RightCBracket => {
+					self.depth = self.depth.saturating_sub(1)
+				}
+				LexemeType::Prefix(_) | LexemeType::Dispatch(_) => self.awaiting_datum = true,
+				_ => {}
+			}
+			// Only move past whole lexemes, anything after them may still change.
+			self.scanned = start + lexer.position();
+			if self.depth == 0 && !self.awaiting_datum {
+				return Ok(Some(self.scanned));
+			}
+		}
+	}
+
+	fn take_expression(
+		&mut self,
+		end: usize,
+		macros: Option<&mut dyn ReaderMacros>,
+	) -> Result<Expression, ParsingError> {
+		let lexemes: Vec<Lexeme> = self
+			.lexer(&self.buffer[..end], self.consumed)
+			.collect::<Result<_, LexingError>>()
+			.map_err(|e| e.offset_by(self.consumed))?;
+		let mut parser = Parser::new(lexemes.as_slice());
+		if let Some(macros) = macros {
+			parser = parser.with_reader_macros(macros);
+		}
+		let expression = parser.parse_expression();
+		self.buffer.drain(..end);
+		self.consumed += end;
+		self.scanned = 0;
+		expression
+	}
+
+	/// Reads the next expression, expanding reader macros through `macros`.
+	pub fn read_with(
+		&mut self,
+		macros: Option<&mut dyn ReaderMacros>,
+	) -> Option<Result<Expression, ParsingError>> {
+		loop {
+			match self.scan() {
+				Ok(Some(end)) => return Some(self.take_expression(end, macros)),
+				Ok(None) => {}
+				Err(e) => {
+					// Nothing after a lexing error can be trusted, stop reading.
+					self.discard_buffer();
+					self.eof = true;
+					return Some(Err(e.into()));
+				}
+			}
+
+			if self.eof {
+				if self.depth > 0 || self.awaiting_datum {
+					self.discard_buffer();
+					return Some(Err(ParsingError::UnexpectedEof));
+				}
+				return None;
+			}
+
+			match self.input.read_line(&mut self.buffer) {
+				Ok(0) => self.eof = true,
+				Ok(_) => {}
+				Err(e) => {
+					self.eof = true;
+					return Some(Err(ParsingError::ReadFailure(e.kind())));
+				}
+			}
+		}
+	}
+}
+
+/// True unless `text` ends inside a string, comment or bracket, or with a
+/// reader macro still waiting for its expression. Text that is wrong in some
+/// other way counts as complete, more input would not fix it.
+pub fn is_complete(text: &str) -> bool {
+	let mut depth = 0usize;
+	let mut awaiting_datum = false;
+	for lexeme in Lexer::new(text) {
+		let lexeme = match lexeme {
+			Ok(lexeme) => lexeme,
+			Err(e) => return *e.kind() != LexingErrorKind::UnexpectedEof,
+		};
+		awaiting_datum = false;
+		match lexeme.value {
+			LexemeType::LeftParen | LexemeType::LeftCBracket => depth += 1,
+			LexemeType::RightParen | LexemeType::RightCBracket => match depth.checked_sub(1) {
+				Some(remaining) => depth = remaining,
+				None => return true,
+			},
+			LexemeType::Prefix(_) | LexemeType::Dispatch(_) => awaiting_datum = true,
+			_ => {}
+		}
+	}
+	depth == 0 && !awaiting_datum
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+	type Item = Result<Expression, ParsingError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.read_with(None)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::numeric::Numeric;
+	use std::collections::VecDeque;
+	use std::io::Cursor;
+
+	fn read_all(text: &str) -> Vec<Result<Expression, ParsingError>> {
+		Reader::new(Cursor::new(text)).collect()
+	}
+
+	fn int(i: i64) -> Expression {
+		Expression::Atom(Atom::Number(Numeric::Int(i)))
+	}
+
+	#[test]
+	fn reads_one_expression_at_a_time() {
+		assert_eq!(
+			vec![
+				Ok(int(1)),
+				Ok(Expression::SExpression(VecDeque::from([
+					Expression::Atom(Atom::Symbol(String::from("+"))),
+					int(2),
+					Expression::QExpression(VecDeque::from([int(3)])),
+				]))),
+				Ok(Expression::Atom(Atom::String(String::from("two\nlines")))),
+			],
+			read_all("1 (+ 2\n; comment )\n{3}) \"two\nlines\"\n#| trailing\n|#")
+		);
+	}
+
+	#[test]
+	fn unterminated_expressions_are_errors() {
+		assert_eq!(
+			vec![Ok(int(1)), Err(ParsingError::UnexpectedEof)],
+			read_all("1\n(+ 2\n")
+		);
+		assert!(matches!(
+			read_all("\"never closed\n").as_slice(),
+			[Err(ParsingError::LexingError(_))]
+		));
+	}
+
+	#[test]
+	fn completeness_follows_brackets_strings_and_macros() {
+		for text in [
+			"",
+			"1 2",
+			"(def {x}\n\t(+ 1 2))",
+			"\"a\nb\"",
+			"(a))",
+			"1x",
+			"#| a |# b",
+		] {
+			assert!(is_complete(text), "{text:?}");
+		}
+		for text in [
+			"(def {x}", "{1 (2}", "\"open", "#| open", "`", "(a #;", "(f \"}\"",
+		] {
+			assert!(!is_complete(text), "{text:?}");
+		}
+	}
+
+	#[test]
+	fn interpreter_line_is_skipped_once() {
+		assert_eq!(
+			vec![
+				Ok(int(1)),
+				Ok(Expression::Atom(Atom::Symbol(String::from("#!x")))),
+			],
+			read_all("#!/usr/bin/env lockjaw\n1\n#!x")
+		);
+	}
+
+	#[test]
+	fn lexing_errors_point_into_the_whole_input() {
+		let errors = read_all("(a)\n(b 1x)");
+		let Some(Err(ParsingError::LexingError(e))) = errors.last() else {
+			panic!("expected lexing error, got {errors:?}");
+		};
+		assert!(e.to_string().starts_with("Error at position 7:"), "{e}");
+	}
+}
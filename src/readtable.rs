@@ -0,0 +1,105 @@
+use crate::lexer::{Lexer, PREFIX_CHARACTERS};
+use crate::types::*;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+/// How a reader macro was invoked, either `@datum` or `#name datum`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroName<'a> {
+	Prefix(char),
+	Dispatch(&'a str),
+}
+
+impl Display for MacroName<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			MacroName::Prefix(c) => write!(f, "{c}"),
+			MacroName::Dispatch(name) => write!(f, "#{name}"),
+		}
+	}
+}
+
+/// Expands reader macros while parsing.
+///
+/// The datum is the expression following the macro, exactly as it was read.
+/// Returns `None` when nothing is registered under `name`.
+pub trait ReaderMacros {
+	fn expand(
+		&mut self,
+		name: MacroName,
+		datum: Expression,
+	) -> Option<Result<Expression, LockjawRuntimeError>>;
+}
+
+/// Functions the parser calls to build expressions for reader macros.
+///
+/// Handlers are ordinary function values. A `ReadTable` on its own can only
+/// call builtins, expanding with user functions needs an `Evaluator`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadTable {
+	prefixes: HashMap<char, Value>,
+	dispatch: HashMap<String, Value>,
+}
+
+impl ReadTable {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn set_prefix(&mut self, prefix: char, handler: Value) -> Result<(), LockjawRuntimeError> {
+		if !PREFIX_CHARACTERS.contains(&prefix) {
+			return Err(LockjawRuntimeError::InvalidArguments(format!(
+				"Reader macro prefix must be one of {PREFIX_CHARACTERS:?}, got {prefix:?}"
+			)));
+		}
+		Self::check_handler(&handler)?;
+		self.prefixes.insert(prefix, handler);
+		Ok(())
+	}
+
+	pub fn set_dispatch(&mut self, name: &str, handler: Value) -> Result<(), LockjawRuntimeError> {
+		// The name must lex as part of a single `#name` symbol for the macro to be found.
+		let mut chars = name.chars();
+		if !chars.next().is_some_and(Lexer::is_symbol_continue)
+			|| !chars.all(Lexer::is_symbol_continue)
+		{
+			return Err(LockjawRuntimeError::InvalidArguments(format!(
+				"Reader macro names may only contain symbol characters, got {name:?}"
+			)));
+		}
+		Self::check_handler(&handler)?;
+		self.dispatch.insert(name.to_string(), handler);
+		Ok(())
+	}
+
+	pub fn get(&self, name: MacroName) -> Option<&Value> {
+		match name {
+			MacroName::Prefix(c) => self.prefixes.get(&c),
+			MacroName::Dispatch(name) => self.dispatch.get(name),
+		}
+	}
+
+	fn check_handler(handler: &Value) -> Result<(), LockjawRuntimeError> {
+		match handler {
+			Value::Builtin(_) | Value::UserDef(_) => Ok(()),
+			_ => Err(LockjawRuntimeError::InvalidFunction(format!(
+				"Reader macros must be functions, got {handler}"
+			))),
+		}
+	}
+}
+
+impl ReaderMacros for ReadTable {
+	fn expand(
+		&mut self,
+		name: MacroName,
+		datum: Expression,
+	) -> Option<Result<Expression, LockjawRuntimeError>> {
+		Some(match self.get(name)? {
+			Value::Builtin(f) => f(VecDeque::from([datum])),
+			handler => Err(LockjawRuntimeError::InvalidFunction(format!(
+				"Reader macro {name} is {handler}, which needs an evaluator to expand"
+			))),
+		})
+	}
+}
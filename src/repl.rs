@@ -0,0 +1,843 @@
+use lockjaw::lexer::{self, Lexeme, LexemeType, Lexer, LexingError, LexingErrorKind};
+use lockjaw::types::{Expression, LockjawRuntimeError};
+use lockjaw::{evaluator::Evaluator, parser, reader};
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{
+	Cmd, ConditionalEventHandler, Config, Context, Editor, Event, EventContext, EventHandler,
+	Helper, KeyCode, KeyEvent, Modifiers, RepeatCount,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const PROMPT: &str = "lj> ";
+// Shown while an expression started on an earlier line is still open.
+const CONTINUATION_PROMPT: &str = "..> ";
+
+// Entries kept between sessions, the oldest are dropped first.
+const HISTORY_SIZE: usize = 1000;
+
+fn history_path() -> Option<PathBuf> {
+	Some(dirs_next::data_dir()?.join("lockjaw").join("history"))
+}
+
+fn rc_path() -> Option<PathBuf> {
+	Some(dirs_next::home_dir()?.join(".lockjawrc"))
+}
+
+fn save_history(rl: &mut Editor<ReplHelper>, path: &Path) -> rustyline::Result<()> {
+	if let Some(directory) = path.parent() {
+		fs::create_dir_all(directory)?;
+	}
+	rl.save_history(path)
+}
+
+// ANSI styles used when highlighting input.
+const NUMBER: &str = "\x1b[33m";
+const STRING: &str = "\x1b[32m";
+const KEYWORD: &str = "\x1b[35m";
+const BOUND: &str = "\x1b[34m";
+const READER_MACRO: &str = "\x1b[36m";
+const COMMENT: &str = "\x1b[90m";
+const ERROR: &str = "\x1b[31m";
+const MATCHING_BRACKET: &str = "\x1b[1;4m";
+const HINT: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+// Lexemes of `line` with the offset each one ends at, and where lexing failed if it did.
+fn lex_line(line: &str) -> (Vec<(Lexeme<'_>, usize)>, Option<LexingError>) {
+	let mut lexer = Lexer::new(line);
+	let mut lexemes = Vec::new();
+	while let Some(lexeme) = lexer.next() {
+		match lexeme {
+			Ok(lexeme) => lexemes.push((lexeme, lexer.position())),
+			Err(e) => return (lexemes, Some(e)),
+		}
+	}
+	(lexemes, None)
+}
+
+fn is_open(value: LexemeType) -> bool {
+	matches!(value, LexemeType::LeftParen | LexemeType::LeftCBracket)
+}
+
+fn is_close(value: LexemeType) -> bool {
+	matches!(value, LexemeType::RightParen | LexemeType::RightCBracket)
+}
+
+// Positions in `lexemes` of the bracket at or just before `pos` and the one it pairs with.
+fn matching_brackets(lexemes: &[(Lexeme, usize)], pos: usize) -> Option<(usize, usize)> {
+	let bracket = |(lexeme, _): &(Lexeme, usize)| is_open(lexeme.value) || is_close(lexeme.value);
+	let at = lexemes
+		.iter()
+		.position(|entry| entry.0.index == pos && bracket(entry))
+		.or_else(|| {
+			lexemes
+				.iter()
+				.position(|entry| entry.1 == pos && bracket(entry))
+		})?;
+	let mut depth = 0usize;
+	if is_open(lexemes[at].0.value) {
+		for (i, (lexeme, _)) in lexemes.iter().enumerate().skip(at) {
+			if is_open(lexeme.value) {
+				depth += 1;
+			} else if is_close(lexeme.value) {
+				depth -= 1;
+				if depth == 0 {
+					return Some((at, i));
+				}
+			}
+		}
+	} else {
+		for (i, (lexeme, _)) in lexemes.iter().enumerate().take(at + 1).rev() {
+			if is_close(lexeme.value) {
+				depth += 1;
+			} else if is_open(lexeme.value) {
+				depth -= 1;
+				if depth == 0 {
+					return Some((i, at));
+				}
+			}
+		}
+	}
+	None
+}
+
+fn paint(output: &mut String, style: Option<&str>, text: &str) {
+	match style {
+		Some(style) => {
+			output.push_str(style);
+			output.push_str(text);
+			output.push_str(RESET);
+		}
+		None => output.push_str(text),
+	}
+}
+
+// Text the lexer skipped over is whitespace or comments. The indentation
+// that starts a continued line is drawn as the continuation prompt, it is as
+// wide as the prompt so the cursor still lines up.
+fn paint_gap(output: &mut String, text: &str) {
+	if text.trim().is_empty() {
+		let mut rows = text.split('\n');
+		output.push_str(rows.next().unwrap_or_default());
+		for row in rows {
+			output.push('\n');
+			let indent =
+				(row.len() - row.trim_start_matches(' ').len()).min(CONTINUATION_PROMPT.len());
+			if indent > 0 {
+				paint(output, Some(HINT), &CONTINUATION_PROMPT[..indent]);
+			}
+			output.push_str(&row[indent..]);
+		}
+	} else {
+		paint(output, Some(COMMENT), text);
+	}
+}
+
+// Completes, highlights and hints using what is bound in the evaluator, and
+// completes file paths inside the string given to `load`.
+struct ReplHelper {
+	// Refreshed before every read, the evaluator is busy while a line is edited.
+	names: Vec<String>,
+	signatures: HashMap<String, String>,
+	filenames: FilenameCompleter,
+}
+
+impl ReplHelper {
+	fn new() -> Self {
+		ReplHelper {
+			names: Vec::new(),
+			signatures: HashMap::new(),
+			filenames: FilenameCompleter::new(),
+		}
+	}
+
+	fn refresh(&mut self, environment: &Evaluator) {
+		self.names = environment.bound_names();
+		self.signatures = self
+			.names
+			.iter()
+			.filter_map(|name| Some((name.clone(), environment.signature(name)?)))
+			.collect();
+	}
+
+	fn style(&self, value: LexemeType) -> Option<&'static str> {
+		match value {
+			LexemeType::Integer(_) | LexemeType::Float(_) => Some(NUMBER),
+			LexemeType::RawSymbol("#t" | "#f") => Some(NUMBER),
+			LexemeType::StringLiteral(_)
+			| LexemeType::RawStringLiteral(_)
+			| LexemeType::MultilineStringLiteral(_)
+			| LexemeType::InterpolatedStringLiteral(_) => Some(STRING),
+			LexemeType::Keyword(_) => Some(KEYWORD),
+			LexemeType::RawSymbol(symbol) => self
+				.names
+				.binary_search_by(|name| name.as_str().cmp(symbol))
+				.ok()
+				.map(|_| BOUND),
+			LexemeType::Prefix(_) | LexemeType::Dispatch(_) => Some(READER_MACRO),
+			_ => None,
+		}
+	}
+
+	// True when the cursor is inside an unterminated string that follows `load` or `require`.
+	fn in_load_path(line: &str) -> bool {
+		let mut previous = None;
+		for lexeme in Lexer::new(line) {
+			match lexeme {
+				Ok(lexeme) => previous = Some(lexeme.value),
+				Err(e) => {
+					return *e.kind() == LexingErrorKind::UnexpectedEof
+						&& matches!(previous, Some(LexemeType::RawSymbol("load" | "require")))
+				}
+			}
+		}
+		false
+	}
+}
+
+impl Completer for ReplHelper {
+	type Candidate = Pair;
+
+	fn complete(
+		&self,
+		line: &str,
+		pos: usize,
+		ctx: &Context<'_>,
+	) -> rustyline::Result<(usize, Vec<Pair>)> {
+		if Self::in_load_path(&line[..pos]) {
+			return self.filenames.complete(line, pos, ctx);
+		}
+		let start = line[..pos]
+			.rfind(|c| !Lexer::is_symbol_continue(c))
+			.map_or(0, |i| i + line[i..].chars().next().unwrap().len_utf8());
+		let prefix = &line[start..pos];
+		if &line[..start] == ":" {
+			let candidates = META_COMMANDS
+				.iter()
+				.filter(|command| command.0.starts_with(prefix))
+				.map(|command| Pair {
+					display: command.0.to_string(),
+					replacement: command.0.to_string(),
+				})
+				.collect();
+			return Ok((start, candidates));
+		}
+		let candidates = self
+			.names
+			.iter()
+			.filter(|name| name.starts_with(prefix))
+			.map(|name| Pair {
+				display: name.clone(),
+				replacement: name.clone(),
+			})
+			.collect();
+		Ok((start, candidates))
+	}
+}
+
+impl Hinter for ReplHelper {
+	type Hint = String;
+
+	// The signature of the function whose call the cursor is in.
+	fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+		if pos < line.len() {
+			return None;
+		}
+		let (lexemes, _) = lex_line(line);
+		// A line is an SExpression of its own, so it counts as an open call.
+		let mut open = vec![(LexemeType::LeftParen, 0)];
+		for (i, (lexeme, _)) in lexemes.iter().enumerate() {
+			if is_open(lexeme.value) {
+				open.push((lexeme.value, i + 1));
+			} else if is_close(lexeme.value) && open.len() > 1 {
+				open.pop();
+			}
+		}
+		let (LexemeType::LeftParen, head) = *open.last()? else {
+			return None;
+		};
+		let LexemeType::RawSymbol(function) = lexemes.get(head)?.0.value else {
+			return None;
+		};
+		Some(format!("  {}", self.signatures.get(function)?))
+	}
+}
+
+impl Highlighter for ReplHelper {
+	fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+		let (lexemes, error) = lex_line(line);
+		let matching = matching_brackets(&lexemes, pos);
+		let mut output = String::with_capacity(line.len() * 2);
+		let mut last = 0;
+		for (i, (lexeme, end)) in lexemes.iter().enumerate() {
+			paint_gap(&mut output, &line[last..lexeme.index]);
+			let style = match matching {
+				Some((open, close)) if i == open || i == close => Some(MATCHING_BRACKET),
+				_ => self.style(lexeme.value),
+			};
+			paint(&mut output, style, &line[last.max(lexeme.index)..*end]);
+			last = *end;
+		}
+		match error {
+			Some(e) => {
+				let at = e.position().clamp(last, line.len());
+				paint_gap(&mut output, &line[last..at]);
+				// Strings and comments still being typed are not mistakes yet.
+				let style = match e.kind() {
+					LexingErrorKind::UnexpectedEof if line[at..].starts_with("#") => {
+						if line[at..].starts_with("#r") {
+							STRING
+						} else {
+							COMMENT
+						}
+					}
+					LexingErrorKind::UnexpectedEof => STRING,
+					LexingErrorKind::InvalidLiteral { .. } => ERROR,
+				};
+				paint(&mut output, Some(style), &line[at..]);
+			}
+			None => paint_gap(&mut output, &line[last..]),
+		}
+		Cow::Owned(output)
+	}
+
+	fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+		Cow::Owned(format!("{HINT}{hint}{RESET}"))
+	}
+
+	// Bracket matching follows the cursor, so every move needs a redraw.
+	fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+		true
+	}
+}
+
+// Enter on an unfinished expression starts a new line in the same buffer, so
+// the whole form can still be edited before it is evaluated.
+impl Validator for ReplHelper {
+	fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+		Ok(if reader::is_complete(ctx.input()) {
+			ValidationResult::Valid(None)
+		} else {
+			ValidationResult::Incomplete
+		})
+	}
+}
+
+impl Helper for ReplHelper {}
+
+// rustyline has no prompt for the lines after the first, so Enter at the end
+// of an unfinished expression starts the next line with room for one, which
+// the highlighter fills in. Inside a string or comment the line is left
+// alone, the spaces would become part of it.
+struct ContinueForm;
+
+impl ConditionalEventHandler for ContinueForm {
+	fn handle(
+		&self,
+		_evt: &Event,
+		_n: RepeatCount,
+		_positive: bool,
+		ctx: &EventContext,
+	) -> Option<Cmd> {
+		let line = ctx.line();
+		if ctx.pos() < line.len() || reader::is_complete(line) || lex_line(line).1.is_some() {
+			return None;
+		}
+		Some(Cmd::Insert(
+			1,
+			format!("\n{}", " ".repeat(CONTINUATION_PROMPT.len())),
+		))
+	}
+}
+
+// Reads lines until they form complete expressions, so a `def` spread over
+// several lines is evaluated as one unit. `None` once input has ended.
+// At a terminal `ContinueForm` and the validator already hold Enter back until
+// the form is complete, the secondary prompt is for input that is piped in,
+// which rustyline reads without validating.
+fn read_form(rl: &mut Editor<ReplHelper>) -> Option<String> {
+	let mut form = String::new();
+	loop {
+		let prompt = if form.is_empty() {
+			PROMPT
+		} else {
+			CONTINUATION_PROMPT
+		};
+		match rl.readline(prompt) {
+			Ok(line) => {
+				form.push_str(&line);
+				if reader::is_complete(&form) {
+					rl.add_history_entry(form.as_str());
+					return Some(form);
+				}
+				form.push('\n');
+			}
+			// CTRL+C abandons a half typed expression, or closes the REPL at the prompt.
+			Err(ReadlineError::Interrupted) if !form.is_empty() => form.clear(),
+			Err(ReadlineError::Interrupted) => {
+				println!("CTRL+C! Closing.");
+				return None;
+			}
+			Err(ReadlineError::Eof) => {
+				println!("EOF");
+				return None;
+			}
+			Err(why) => {
+				print_error(&format!("could not read input: {why}"));
+				return None;
+			}
+		}
+	}
+}
+
+// Colon commands understood by the REPL itself, with their arguments and what they do.
+// Only these exact names are commands, any other keyword is evaluated as usual.
+const META_COMMANDS: [(&str, &str, &str); 10] = [
+	("env", "", "list every binding in scope"),
+	(
+		"type",
+		"expr",
+		"evaluate expr and show the type of the result",
+	),
+	(
+		"time",
+		"expr",
+		"evaluate expr and show wall time and evaluation steps",
+	),
+	("tokens", "text", "show the lexemes of text"),
+	("ast", "text", "show the expression text parses to"),
+	("load", "path", "evaluate the file at path"),
+	("reload", "", "evaluate the last loaded file again"),
+	("reset", "", "start over with a fresh evaluator"),
+	("help", "", "show this list"),
+	("quit", "", "leave the REPL"),
+];
+
+/// How the REPL starts up and shows results.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+	/// Evaluate `~/.lockjawrc` before the first prompt.
+	pub load_rc: bool,
+	/// Follow each result with its type.
+	pub verbose: bool,
+	/// Evaluate the standard prelude when `:reset` starts over.
+	pub prelude: bool,
+}
+
+/// Reports an error on stderr, in red when that is a terminal.
+pub fn print_error(why: &dyn Display) {
+	if io::stderr().is_terminal() {
+		eprintln!("{ERROR}error:{RESET} {why}");
+	} else {
+		eprintln!("error: {why}");
+	}
+}
+
+struct Session {
+	environment: Evaluator,
+	options: Options,
+	// Set by `:load`, for `:reload`.
+	last_load: Option<PathBuf>,
+	// Set once the program calls `exit`.
+	exit: Option<i32>,
+}
+
+impl Session {
+	fn new(environment: Evaluator, options: Options) -> Self {
+		let mut session = Session {
+			environment,
+			options,
+			last_load: None,
+			exit: None,
+		};
+		session.run_rc();
+		session
+	}
+
+	fn run_rc(&mut self) {
+		if let Some(rc) = rc_path().filter(|rc| self.options.load_rc && rc.is_file()) {
+			if let Err(why) = self.environment.load_file(&rc) {
+				print_error(&format!("in {}: {why}", rc.display()));
+			}
+		}
+	}
+
+	fn parse(&mut self, text: &str) -> Option<Expression> {
+		let lexemes: Result<Vec<lexer::Lexeme>, LexingError> = lexer::Lexer::new(text).collect();
+		match lexemes {
+			Ok(lexemes) => match parser::Parser::new(&lexemes)
+				.with_reader_macros(&mut self.environment)
+				.parse_all()
+			{
+				Ok(lj) => Some(lj),
+				Err(parser_err) => {
+					print_error(&parser_err);
+					None
+				}
+			},
+			Err(why) => {
+				print_error(&why);
+				None
+			}
+		}
+	}
+
+	fn evaluate(&mut self, text: &str) -> Option<Expression> {
+		let lj = self.parse(text)?;
+		match self.environment.evaluate(lj) {
+			Ok(result) => Some(result),
+			Err(LockjawRuntimeError::Exit(code)) => {
+				self.exit = Some(code);
+				None
+			}
+			Err(why) => {
+				print_error(&why);
+				None
+			}
+		}
+	}
+
+	// Results are written so they read back the same, `()` from forms like
+	// `def` that are run for their effect is left out.
+	fn print_result(&self, result: &Expression) {
+		match result {
+			Expression::Null => {}
+			result if self.options.verbose => {
+				println!("{} : {}", result.write(), result.type_name())
+			}
+			result => println!("{}", result.write()),
+		}
+	}
+
+	fn load(&mut self, path: PathBuf) {
+		match self.environment.load_file(&path) {
+			Ok(_) => println!("Loaded {}", path.display()),
+			Err(why) => print_error(&format!("in {}: {why}", path.display())),
+		}
+		self.last_load = Some(path);
+	}
+
+	// Runs `:name argument`, false once the REPL should stop. `None` when
+	// `name` is not exactly a command or the `:` is not at the start of the
+	// line, the line is then evaluated as usual.
+	fn meta_command(&mut self, form: &str) -> Option<bool> {
+		let (name, argument) = form
+			.strip_prefix(':')?
+			.split_once(char::is_whitespace)
+			.unwrap_or((&form[1..], ""));
+		let argument = argument.trim();
+		META_COMMANDS.iter().find(|command| command.0 == name)?;
+		match name {
+			"env" => {
+				for name in self.environment.bound_names() {
+					if let Some(value) = self.environment.lookup(&name) {
+						println!("{name} = {value}");
+					}
+				}
+			}
+			"type" => {
+				if let Some(result) = self.evaluate(argument) {
+					println!("{}", result.type_name());
+				}
+			}
+			"time" => {
+				let steps = self.environment.steps();
+				let start = Instant::now();
+				let result = self.evaluate(argument);
+				let elapsed = start.elapsed();
+				if let Some(result) = result {
+					self.print_result(&result);
+				}
+				println!(
+					"took {elapsed:?}, {} evaluation steps",
+					self.environment.steps() - steps
+				);
+			}
+			"tokens" => match lexer::Lexer::new(argument).collect::<Result<Vec<_>, _>>() {
+				Ok(lexemes) => {
+					for lexeme in lexemes {
+						println!("{:>4} {:?}", lexeme.index, lexeme.value);
+					}
+				}
+				Err(why) => print_error(&why),
+			},
+			"ast" => {
+				if let Some(lj) = self.parse(argument) {
+					println!("{lj:#?}");
+				}
+			}
+			"load" => self.load(PathBuf::from(argument)),
+			"reload" => match self.last_load.clone() {
+				Some(path) => self.load(path),
+				None => println!("Nothing has been loaded with :load yet"),
+			},
+			"reset" => {
+				let search_path = self.environment.search_path().to_vec();
+				self.environment = if self.options.prelude {
+					Evaluator::new()
+				} else {
+					Evaluator::without_prelude()
+				};
+				self.environment.set_search_path(search_path);
+				self.last_load = None;
+				self.run_rc();
+			}
+			"help" => {
+				for (name, argument, help) in META_COMMANDS {
+					println!("  :{:<14} {help}", format!("{name} {argument}"));
+				}
+			}
+			"quit" => return Some(false),
+			_ => unreachable!("every command in META_COMMANDS is handled"),
+		}
+		Some(true)
+	}
+}
+
+/// Runs the REPL until input ends, returning the status the process should exit with.
+pub fn run(environment: Evaluator, options: Options) -> i32 {
+	let mut session = Session::new(environment, options);
+
+	let config = Config::builder()
+		.max_history_size(HISTORY_SIZE)
+		.history_ignore_dups(true)
+		.build();
+	let mut rl = match Editor::<ReplHelper>::with_config(config) {
+		Ok(rl) => rl,
+		Err(why) => {
+			print_error(&format!("could not create prompt: {why}"));
+			return 1;
+		}
+	};
+	rl.set_helper(Some(ReplHelper::new()));
+	rl.bind_sequence(
+		KeyEvent(KeyCode::Enter, Modifiers::NONE),
+		EventHandler::Conditional(Box::new(ContinueForm)),
+	);
+	let history = history_path();
+	if let Some(path) = &history {
+		// There is nothing to load on the first run.
+		let _ = rl.load_history(path);
+	}
+
+	loop {
+		if let Some(helper) = rl.helper_mut() {
+			helper.refresh(&session.environment);
+		}
+		let Some(form) = read_form(&mut rl) else {
+			break;
+		};
+		match session.meta_command(form.trim_end()) {
+			Some(true) => {}
+			Some(false) => break,
+			None => {
+				if let Some(result) = session.evaluate(&form) {
+					session.print_result(&result);
+				}
+			}
+		}
+		if session.exit.is_some() {
+			break;
+		}
+	}
+
+	if let Some(path) = &history {
+		if let Err(why) = save_history(&mut rl, path) {
+			print_error(&format!(
+				"could not save history to {}: {why}",
+				path.display()
+			));
+		}
+	}
+	session.exit.unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scratch::ScratchDir;
+	use rustyline::history::History;
+
+	fn complete(helper: &ReplHelper, line: &str) -> (usize, Vec<String>) {
+		let history = History::new();
+		let (start, candidates) = helper
+			.complete(line, line.len(), &Context::new(&history))
+			.unwrap();
+		(
+			start,
+			candidates
+				.into_iter()
+				.map(|pair| pair.replacement)
+				.collect(),
+		)
+	}
+
+	#[test]
+	fn completes_bound_names() {
+		let mut environment = Evaluator::new();
+		let definition = environment.parse("def {keyword-count} 1").unwrap();
+		environment.evaluate(definition).unwrap();
+		let mut helper = ReplHelper::new();
+		helper.refresh(&environment);
+
+		assert_eq!(
+			(
+				1,
+				vec![
+					String::from("keyword->string"),
+					String::from("keyword->symbol"),
+					String::from("keyword-count"),
+					String::from("keyword?"),
+				]
+			),
+			complete(&helper, "(keyw")
+		);
+		assert_eq!(
+			(5, vec![String::from("eval")]),
+			complete(&helper, "(+ 1 ev")
+		);
+	}
+
+	fn helper_with(definitions: &str) -> ReplHelper {
+		let mut environment = Evaluator::new();
+		let parsed = environment.parse(definitions).unwrap();
+		environment.evaluate(parsed).unwrap();
+		let mut helper = ReplHelper::new();
+		helper.refresh(&environment);
+		helper
+	}
+
+	#[test]
+	fn highlights_by_lexeme() {
+		let helper = ReplHelper::new();
+		assert_eq!(
+			format!("({NUMBER}1{RESET} {STRING}\"a\"{RESET} {KEYWORD}:k{RESET} x){COMMENT} ; note{RESET}"),
+			helper.highlight("(1 \"a\" :k x) ; note", 2)
+		);
+		assert_eq!(
+			format!("(x {ERROR}1x){RESET}"),
+			helper.highlight("(x 1x)", 0)
+		);
+		assert_eq!(
+			format!("(x {STRING}\"open{RESET}"),
+			helper.highlight("(x \"open", 0)
+		);
+	}
+
+	#[test]
+	fn continued_lines_show_the_continuation_prompt() {
+		let helper = ReplHelper::new();
+		assert_eq!(
+			format!(
+				"{MATCHING_BRACKET}({RESET}{NUMBER}1{RESET}\n{HINT}..> {RESET}{NUMBER}2{RESET}\n{HINT}..{RESET}{MATCHING_BRACKET}){RESET}"
+			),
+			helper.highlight("(1\n    2\n  )", 0)
+		);
+		// Spaces inside a string are its own.
+		assert_eq!(
+			format!("{STRING}\"a\n    b\"{RESET}"),
+			helper.highlight("\"a\n    b\"", 0)
+		);
+	}
+
+	#[test]
+	fn highlights_bound_names_and_matching_brackets() {
+		let helper = helper_with("def {twice} (fun {n} {* 2 n})");
+		assert_eq!(
+			format!(
+				"{MATCHING_BRACKET}({RESET}{BOUND}twice{RESET} {{y}}{MATCHING_BRACKET}){RESET}"
+			),
+			helper.highlight("(twice {y})", 11)
+		);
+		assert_eq!(
+			format!(
+				"({BOUND}twice{RESET} {MATCHING_BRACKET}{{{RESET}y{MATCHING_BRACKET}}}{RESET})"
+			),
+			helper.highlight("(twice {y})", 7)
+		);
+	}
+
+	#[test]
+	fn hints_signature_of_enclosing_call() {
+		let helper = helper_with("def {add3} (fun {a b c} {+ a b c})");
+		let history = History::new();
+		let context = Context::new(&history);
+		let hint = |line: &str| helper.hint(line, line.len(), &context);
+
+		assert_eq!(Some(String::from("  (add3 a b c)")), hint("(add3 1 "));
+		assert_eq!(
+			Some(String::from("  (join {list} {list})")),
+			hint("(add3 (join {1} ")
+		);
+		assert_eq!(
+			Some(String::from("  (add3 a b c)")),
+			hint("(add3 (join {1} {2}) ")
+		);
+		assert_eq!(
+			Some(String::from("  (def {name...} value...)")),
+			hint("def {x} ")
+		);
+		assert_eq!(None, hint("(add3 {1 "));
+		assert_eq!(None, hint("(unbound "));
+	}
+
+	#[test]
+	fn meta_commands_are_recognised_by_name() {
+		let mut session = Session::new(
+			Evaluator::new(),
+			Options {
+				load_rc: false,
+				verbose: false,
+				prelude: true,
+			},
+		);
+		assert_eq!(Some(true), session.meta_command(":time (+ 1 2)"));
+		assert_eq!(Some(true), session.meta_command(":reset"));
+		assert_eq!(Some(false), session.meta_command(":quit"));
+		// Other keywords, even ones starting with a command's name, are evaluated.
+		assert_eq!(None, session.meta_command(":foo"));
+		assert_eq!(None, session.meta_command(":env-x"));
+		assert_eq!(None, session.meta_command(" :quit"));
+		assert_eq!(None, session.meta_command("(+ 1 2)"));
+
+		let steps = session.environment.steps();
+		session.meta_command(":type {1 2}");
+		assert!(session.environment.steps() > steps);
+		assert_eq!(
+			(
+				1,
+				vec![
+					String::from("type"),
+					String::from("time"),
+					String::from("tokens"),
+				]
+			),
+			complete(&ReplHelper::new(), ":t")
+		);
+	}
+
+	#[test]
+	fn completes_paths_given_to_load() {
+		assert!(ReplHelper::in_load_path("(load \"progr"));
+		assert!(ReplHelper::in_load_path("load \""));
+		assert!(ReplHelper::in_load_path("(require \"lib/"));
+		assert!(!ReplHelper::in_load_path("(display \"progr"));
+		assert!(!ReplHelper::in_load_path("(load \"done\" "));
+
+		let root = ScratchDir::new("complete", &[("programs/a.lisp", "")]);
+		let line = format!("(load \"{}/program", root.display());
+		let (start, paths) = complete(&ReplHelper::new(), &line);
+		assert_eq!(7, start);
+		assert_eq!(vec![format!("{}/programs/", root.display())], paths);
+	}
+}
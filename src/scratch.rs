@@ -0,0 +1,47 @@
+//! Temporary directories for tests that need files on disk.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Tests run in parallel threads of one process, the count keeps their
+// directories apart even when two tests pass the same name.
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory of its own under the system temporary directory, removed with
+/// everything in it when dropped.
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+	/// A fresh directory holding `files`, named after the test using it.
+	pub fn new(test: &str, files: &[(&str, &str)]) -> Self {
+		let root = std::env::temp_dir().join(format!(
+			"lockjaw-{}-{}-{test}",
+			std::process::id(),
+			NEXT.fetch_add(1, Ordering::Relaxed)
+		));
+		let _ = fs::remove_dir_all(&root);
+		fs::create_dir_all(&root).unwrap();
+		for (name, contents) in files {
+			let path = root.join(name);
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, contents).unwrap();
+		}
+		ScratchDir(root)
+	}
+}
+
+impl Deref for ScratchDir {
+	type Target = Path;
+
+	fn deref(&self) -> &Path {
+		&self.0
+	}
+}
+
+impl Drop for ScratchDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
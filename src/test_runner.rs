@@ -0,0 +1,352 @@
+//! `lockjaw test`, runs the `deftest` forms of `*_test.lisp` files.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use lockjaw::evaluator::Evaluator;
+use lockjaw::types::LockjawRuntimeError;
+
+use crate::repl::print_error;
+
+/// Files run by `lockjaw test` end with this.
+const TEST_SUFFIX: &str = "_test.lisp";
+
+/// How the tests are selected, evaluated and reported.
+pub struct Options {
+	/// Only run tests whose name contains this.
+	pub filter: Option<String>,
+	/// Also write the results to this file as JUnit XML.
+	pub junit: Option<PathBuf>,
+	pub prelude: bool,
+	pub search_path: Vec<PathBuf>,
+}
+
+// The result of one test, or of loading a file that couldn't be loaded.
+struct Outcome {
+	file: PathBuf,
+	name: String,
+	failure: Option<String>,
+	time: Duration,
+}
+
+// Test files in `paths`, directories are searched recursively skipping hidden ones.
+fn discover(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+	let mut files = Vec::new();
+	for path in paths {
+		if path.is_dir() {
+			let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+				.map(|entry| entry.map(|entry| entry.path()))
+				.collect::<io::Result<_>>()?;
+			entries.retain(|entry| {
+				let name = entry.file_name().unwrap_or_default().to_string_lossy();
+				!name.starts_with('.') && (entry.is_dir() || name.ends_with(TEST_SUFFIX))
+			});
+			entries.sort();
+			files.extend(discover(&entries)?);
+		} else {
+			files.push(path.clone());
+		}
+	}
+	Ok(files)
+}
+
+fn evaluator(options: &Options) -> Evaluator {
+	let mut environment = if options.prelude {
+		Evaluator::new()
+	} else {
+		Evaluator::without_prelude()
+	};
+	environment.set_search_path(options.search_path.clone());
+	environment
+}
+
+fn describe(why: &LockjawRuntimeError) -> String {
+	match why {
+		LockjawRuntimeError::Exit(code) => format!("called exit with status {code}"),
+		why => why.to_string(),
+	}
+}
+
+// The file is evaluated once, so its top-level forms run once. Every test
+// gets a copy of the evaluator it left so no test sees what another left behind.
+fn run_file(file: &Path, options: &Options) -> Vec<Outcome> {
+	let outcome = |name: &str, failure, time| Outcome {
+		file: file.to_path_buf(),
+		name: name.to_string(),
+		failure,
+		time,
+	};
+	let start = Instant::now();
+	let mut loaded = evaluator(options);
+	if let Err(why) = loaded.load_file(file) {
+		return vec![outcome("<load>", Some(describe(&why)), start.elapsed())];
+	}
+	let names: Vec<String> = loaded.test_names().into_iter().map(String::from).collect();
+	let mut outcomes = Vec::new();
+	for (index, name) in names.iter().enumerate() {
+		if options
+			.filter
+			.as_ref()
+			.is_some_and(|filter| !name.contains(filter.as_str()))
+		{
+			continue;
+		}
+		let start = Instant::now();
+		let result = loaded.clone().run_test(index);
+		outcomes.push(outcome(
+			name,
+			result.err().map(|why| describe(&why)),
+			start.elapsed(),
+		));
+	}
+	outcomes
+}
+
+fn escape_xml(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+// One `testsuite` per file, in the order the files were run.
+fn junit(outcomes: &[Outcome]) -> String {
+	let failures = outcomes.iter().filter(|o| o.failure.is_some()).count();
+	let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	let _ = writeln!(
+		xml,
+		"<testsuites tests=\"{}\" failures=\"{failures}\">",
+		outcomes.len()
+	);
+	let mut rest = outcomes;
+	while let Some(first) = rest.first() {
+		let count = rest.iter().take_while(|o| o.file == first.file).count();
+		let (suite, remaining) = rest.split_at(count);
+		rest = remaining;
+		let file = escape_xml(&first.file.display().to_string());
+		let _ = writeln!(
+			xml,
+			"  <testsuite name=\"{file}\" tests=\"{}\" failures=\"{}\">",
+			suite.len(),
+			suite.iter().filter(|o| o.failure.is_some()).count()
+		);
+		for outcome in suite {
+			let _ = write!(
+				xml,
+				"    <testcase name=\"{}\" classname=\"{file}\" time=\"{:.3}\"",
+				escape_xml(&outcome.name),
+				outcome.time.as_secs_f64()
+			);
+			match &outcome.failure {
+				Some(failure) => {
+					let message = failure.lines().next().unwrap_or_default();
+					let _ = writeln!(
+						xml,
+						">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+						escape_xml(message),
+						escape_xml(failure)
+					);
+				}
+				None => xml.push_str("/>\n"),
+			}
+		}
+		xml.push_str("  </testsuite>\n");
+	}
+	xml.push_str("</testsuites>\n");
+	xml
+}
+
+/// Runs the tests found in `paths`, the working directory when there are none.
+/// Returns the exit status, 1 when any test failed.
+pub fn run(paths: &[PathBuf], options: &Options) -> i32 {
+	let paths = if paths.is_empty() {
+		vec![PathBuf::from(".")]
+	} else {
+		paths.to_vec()
+	};
+	let files = match discover(&paths) {
+		Ok(files) => files,
+		Err(why) => {
+			print_error(&why);
+			return 1;
+		}
+	};
+
+	let mut outcomes = Vec::new();
+	for file in &files {
+		for outcome in run_file(file, options) {
+			let status = if outcome.failure.is_some() {
+				"FAILED"
+			} else {
+				"ok"
+			};
+			println!("test {} :: {} ... {status}", file.display(), outcome.name);
+			outcomes.push(outcome);
+		}
+	}
+
+	let failed: Vec<&Outcome> = outcomes.iter().filter(|o| o.failure.is_some()).collect();
+	if !failed.is_empty() {
+		println!("\nfailures:");
+		for outcome in &failed {
+			println!(
+				"\n---- {} :: {} ----\n{}",
+				outcome.file.display(),
+				outcome.name,
+				outcome.failure.as_deref().unwrap_or_default()
+			);
+		}
+	}
+	println!(
+		"\ntest result: {}. {} passed; {} failed",
+		if failed.is_empty() { "ok" } else { "FAILED" },
+		outcomes.len() - failed.len(),
+		failed.len()
+	);
+
+	if let Some(path) = &options.junit {
+		if let Err(why) = fs::write(path, junit(&outcomes)) {
+			print_error(&format!("{}: {why}", path.display()));
+			return 1;
+		}
+	}
+	i32::from(!failed.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scratch::ScratchDir;
+
+	fn options(filter: Option<&str>) -> Options {
+		Options {
+			filter: filter.map(String::from),
+			junit: None,
+			prelude: true,
+			search_path: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn discovers_test_files_recursively() {
+		let root = ScratchDir::new(
+			"discover",
+			&[
+				("a_test.lisp", ""),
+				("a.lisp", ""),
+				("nested/b_test.lisp", ""),
+				(".hidden/c_test.lisp", ""),
+			],
+		);
+		let files = discover(&[root.to_path_buf()]).unwrap();
+		assert_eq!(
+			files,
+			[root.join("a_test.lisp"), root.join("nested/b_test.lisp")]
+		);
+	}
+
+	#[test]
+	fn each_test_runs_in_a_fresh_evaluator() {
+		let root = ScratchDir::new(
+			"run",
+			&[(
+				"math_test.lisp",
+				"(def {n} 1)
+				(deftest \"adds\" {(assert-eq 2 (+ n 1))})
+				(deftest \"leaks\" {(def {n} 5) (assert-true (eq? n 5))})
+				(deftest \"sees no leak\" {(assert-eq 1 n)})
+				(deftest \"fails\" {(assert-eq {1 2} (list 1 3))})
+				(deftest \"errors\" {(assert-error {car 1})})
+				(deftest \"needs an error\" {(assert-error {+ 1 2})})",
+			)],
+		);
+		let outcomes = run_file(&root.join("math_test.lisp"), &options(None));
+		let results: Vec<(&str, bool)> = outcomes
+			.iter()
+			.map(|o| (o.name.as_str(), o.failure.is_none()))
+			.collect();
+		assert_eq!(
+			results,
+			[
+				("adds", true),
+				("leaks", true),
+				("sees no leak", true),
+				("fails", false),
+				("errors", true),
+				("needs an error", false),
+			]
+		);
+		assert_eq!(
+			outcomes[3].failure.as_deref(),
+			Some("assertion failed: values differ\n  - expected: {1 2}\n  + actual:   {1 3}")
+		);
+
+		let filtered = run_file(&root.join("math_test.lisp"), &options(Some("error")));
+		assert_eq!(filtered.len(), 2);
+	}
+
+	#[test]
+	fn files_that_fail_to_load_are_reported() {
+		let root = ScratchDir::new("broken", &[("broken_test.lisp", "(deftest \"x\" {}")]);
+		let outcomes = run_file(&root.join("broken_test.lisp"), &options(None));
+		assert_eq!(outcomes.len(), 1);
+		assert_eq!(outcomes[0].name, "<load>");
+		assert!(outcomes[0].failure.is_some());
+
+		let root = ScratchDir::new("exit", &[("exit_test.lisp", "(deftest \"x\" {}) (exit 3)")]);
+		let outcomes = run_file(&root.join("exit_test.lisp"), &options(None));
+		assert_eq!(outcomes.len(), 1);
+		assert_eq!(outcomes[0].name, "<load>");
+		assert_eq!(
+			outcomes[0].failure.as_deref(),
+			Some("called exit with status 3")
+		);
+	}
+
+	#[test]
+	fn junit_groups_tests_by_file() {
+		let outcome = |file: &str, name: &str, failure: Option<&str>| Outcome {
+			file: PathBuf::from(file),
+			name: name.to_string(),
+			failure: failure.map(String::from),
+			time: Duration::from_millis(2),
+		};
+		let xml = junit(&[
+			outcome("a_test.lisp", "one", None),
+			outcome(
+				"a_test.lisp",
+				"<two>",
+				Some("values differ\n  - expected: \"a\""),
+			),
+			outcome("b_test.lisp", "three", None),
+		]);
+		assert_eq!(
+			xml,
+			"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites tests=\"3\" failures=\"1\">
+  <testsuite name=\"a_test.lisp\" tests=\"2\" failures=\"1\">
+    <testcase name=\"one\" classname=\"a_test.lisp\" time=\"0.002\"/>
+    <testcase name=\"&lt;two&gt;\" classname=\"a_test.lisp\" time=\"0.002\">
+      <failure message=\"values differ\">values differ
+  - expected: &quot;a&quot;</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name=\"b_test.lisp\" tests=\"1\" failures=\"0\">
+    <testcase name=\"three\" classname=\"b_test.lisp\" time=\"0.002\"/>
+  </testsuite>
+</testsuites>
+"
+		);
+	}
+}
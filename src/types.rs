@@ -1,7 +1,11 @@
 use crate::numeric::Numeric;
 use crate::parser::ParsingError;
-use std::collections::VecDeque;
+use crate::printer::{self, AtomPrinter, Printer, Style};
+use std::collections::{HashSet, VecDeque};
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
 
 #[derive(Debug)]
 pub enum LockjawRuntimeError {
@@ -11,7 +15,82 @@ pub enum LockjawRuntimeError {
 	ParserError(ParsingError),
 	FileError(std::io::Error),
 	CondFailure,
-	UnboundExpression,
+	UnboundExpression(String),
+	RecursionLimitExceeded,
+	// Raised by `exit`, unwinds evaluation so the process can end with this status.
+	Exit(i32),
+	// A file loaded itself through the files in between, from the outermost load.
+	LoadCycle(Vec<PathBuf>),
+	// Raised by the `assert-` forms, the message explains what differed.
+	AssertionFailed(String),
+	// Integer arithmetic whose result doesn't fit in an `i64`, with the operator.
+	IntegerOverflow(String),
+	// Raised by `error`, with the message the program gave it.
+	Raised(String),
+	// No file called `name` in any of the directories searched, in search order.
+	FileNotFound {
+		name: String,
+		searched: Vec<PathBuf>,
+	},
+}
+
+impl Display for LockjawRuntimeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			LockjawRuntimeError::InvalidArguments(message) => {
+				write!(f, "invalid arguments: {message}")
+			}
+			LockjawRuntimeError::InvalidArgumentCount(message) => {
+				write!(f, "wrong number of arguments: {message}")
+			}
+			LockjawRuntimeError::InvalidFunction(message) => write!(f, "not a function: {message}"),
+			LockjawRuntimeError::ParserError(e) => write!(f, "parse error: {e}"),
+			LockjawRuntimeError::FileError(e) => write!(f, "file error: {e}"),
+			LockjawRuntimeError::CondFailure => write!(f, "no cond clause matched"),
+			LockjawRuntimeError::UnboundExpression(name) => write!(f, "unbound symbol: {name}"),
+			LockjawRuntimeError::RecursionLimitExceeded => write!(
+				f,
+				"recursion limit of {} calls exceeded",
+				crate::evaluator::MAX_CALL_DEPTH
+			),
+			LockjawRuntimeError::Exit(code) => write!(f, "exit with status {code}"),
+			LockjawRuntimeError::LoadCycle(chain) => {
+				write!(f, "load cycle: ")?;
+				for (i, path) in chain.iter().enumerate() {
+					if i > 0 {
+						write!(f, " -> ")?;
+					}
+					write!(f, "{}", path.display())?;
+				}
+				Ok(())
+			}
+			LockjawRuntimeError::AssertionFailed(message) => {
+				write!(f, "assertion failed: {message}")
+			}
+			LockjawRuntimeError::IntegerOverflow(operator) => {
+				write!(f, "integer overflow in {operator}")
+			}
+			LockjawRuntimeError::Raised(message) => write!(f, "{message}"),
+			LockjawRuntimeError::FileNotFound { name, searched } => {
+				write!(f, "no file {name}")?;
+				for (i, directory) in searched.iter().enumerate() {
+					write!(f, "{}", if i == 0 { " in " } else { ", " })?;
+					write!(f, "{}", directory.display())?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+impl std::error::Error for LockjawRuntimeError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			LockjawRuntimeError::ParserError(e) => Some(e),
+			LockjawRuntimeError::FileError(e) => Some(e),
+			_ => None,
+		}
+	}
 }
 
 impl From<ParsingError> for LockjawRuntimeError {
@@ -33,8 +112,13 @@ pub struct UserFunc {
 	pub args: VecDeque<Expression>,
 	pub body: VecDeque<Expression>,
 	pub curried: VecDeque<Expression>,
+	// Index of the module whose globals the body is evaluated in, `None`
+	// until the function is handed to the evaluator.
+	pub module: Option<usize>,
 }
 
+// Builtins are compared by address, which is good enough to tell two values apart.
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
 	Builtin(BuiltinFunction),
@@ -44,18 +128,76 @@ pub enum Value {
 	Def,
 	Cond,
 	Load,
+	Require,
+	Module,
+	Import,
+	DefTest,
+	AssertError,
+	SetReaderMacro,
+	SetReaderPrefix,
 	Variable(Box<Expression>),
 }
 
 impl Display for Value {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		match self {
-			Value::Variable(e) => write!(f, "{}", *e),
-			Value::Eval | Value::Def | Value::Load | Value::Cond | Value::Builtin(_) => {
-				write!(f, "<BUILTIN_FUNC>")
-			}
-			Value::UserDef(_) => write!(f, "<USER FUNC>"),
+		printer::fmt_value(self, Style::Write, f)
+	}
+}
+
+/// A self-evaluating name such as `:key`.
+///
+/// Keywords are interned, so comparing two of them is a pointer comparison.
+/// Interned names live for the rest of the program.
+#[derive(Debug, Clone, Eq)]
+pub struct Keyword(Arc<str>);
+
+impl Keyword {
+	pub fn new(name: &str) -> Self {
+		static INTERNED: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+		let mut interned = INTERNED
+			.get_or_init(Default::default)
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner());
+		if let Some(existing) = interned.get(name) {
+			return Keyword(existing.clone());
 		}
+		let name: Arc<str> = Arc::from(name);
+		interned.insert(name.clone());
+		Keyword(name)
+	}
+
+	pub fn name(&self) -> &str {
+		&self.0
+	}
+}
+
+impl PartialEq for Keyword {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0)
+	}
+}
+
+impl Hash for Keyword {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		std::ptr::hash(Arc::as_ptr(&self.0), state)
+	}
+}
+
+impl Display for Keyword {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, ":{}", self.0)
+	}
+}
+
+impl From<&str> for Keyword {
+	fn from(name: &str) -> Self {
+		Keyword::new(name)
+	}
+}
+
+impl From<Keyword> for String {
+	fn from(keyword: Keyword) -> Self {
+		keyword.name().to_string()
 	}
 }
 
@@ -63,13 +205,13 @@ impl Display for Value {
 pub enum Atom {
 	Number(Numeric),
 	Symbol(String),
+	Keyword(Keyword),
 	Value(Value),
 	String(String),
 	Bool(bool),
 }
 
 impl Atom {
-	#[allow(dead_code)]
 	pub fn get_as_symbol(self) -> Result<String, LockjawRuntimeError> {
 		if let Atom::Symbol(symb) = self {
 			Ok(symb)
@@ -81,6 +223,28 @@ impl Atom {
 		}
 	}
 
+	pub fn get_as_keyword(self) -> Result<Keyword, LockjawRuntimeError> {
+		if let Atom::Keyword(keyword) = self {
+			Ok(keyword)
+		} else {
+			Err(LockjawRuntimeError::InvalidArguments(format!(
+				"Expected Keyword, got {}",
+				self
+			)))
+		}
+	}
+
+	pub fn get_as_string(self) -> Result<String, LockjawRuntimeError> {
+		if let Atom::String(s) = self {
+			Ok(s)
+		} else {
+			Err(LockjawRuntimeError::InvalidArguments(format!(
+				"Expected String, got {}",
+				self
+			)))
+		}
+	}
+
 	pub fn get_as_value(self) -> Result<Value, LockjawRuntimeError> {
 		if let Atom::Value(v) = self {
 			Ok(v)
@@ -95,16 +259,11 @@ impl Atom {
 
 impl Display for Atom {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		match self {
-			Atom::Number(num) => write!(f, "Number: {}", num),
-			Atom::Symbol(v) => write!(f, "Symbol: {}", v),
-			Atom::Value(v) => write!(f, "{}", v),
-			Atom::Bool(v) => write!(f, "{}", v),
-			Atom::String(v) => write!(f, "{}", v),
-		}
+		write!(f, "{}", AtomPrinter::new(self, Style::Write))
 	}
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
 	Atom(Atom),
@@ -113,32 +272,84 @@ pub enum Expression {
 	Null,
 }
 
+impl From<i64> for Expression {
+	fn from(i: i64) -> Self {
+		Expression::Atom(Atom::Number(Numeric::Int(i)))
+	}
+}
+
+impl From<f64> for Expression {
+	fn from(f: f64) -> Self {
+		Expression::Atom(Atom::Number(Numeric::Float(f)))
+	}
+}
+
+impl From<bool> for Expression {
+	fn from(b: bool) -> Self {
+		Expression::Atom(Atom::Bool(b))
+	}
+}
+
+impl From<String> for Expression {
+	fn from(s: String) -> Self {
+		Expression::Atom(Atom::String(s))
+	}
+}
+
+impl From<&str> for Expression {
+	fn from(s: &str) -> Self {
+		Expression::Atom(Atom::String(s.to_string()))
+	}
+}
+
+impl From<Keyword> for Expression {
+	fn from(keyword: Keyword) -> Self {
+		Expression::Atom(Atom::Keyword(keyword))
+	}
+}
+
+// Lists become Q-expressions, so they are data rather than calls.
+impl From<Vec<Expression>> for Expression {
+	fn from(items: Vec<Expression>) -> Self {
+		Expression::QExpression(items.into())
+	}
+}
+
 impl Display for Expression {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		match self {
-			Expression::Atom(v) => write!(f, "{}", v),
-			Expression::SExpression(v) => {
-				write!(f, "( ")?;
-				for expr in v {
-					write!(f, "{} ", expr)?;
-				}
-				write!(f, ")")
-			}
-			Expression::QExpression(v) => {
-				write!(f, "{{ ")?;
-				for expr in v {
-					write!(f, "{} ", expr)?;
-				}
-				write!(f, "}}")
-			}
-			Expression::Null => {
-				write!(f, "()")
-			}
-		}
+		write!(f, "{}", self.write())
 	}
 }
 
 impl Expression {
+	/// Printer whose output reads back to an equal expression.
+	pub fn write(&self) -> Printer<'_> {
+		Printer::new(self, Style::Write)
+	}
+
+	/// Printer for end users, strings are not quoted or escaped.
+	pub fn display(&self) -> Printer<'_> {
+		Printer::new(self, Style::Display)
+	}
+
+	/// Name of the kind of value this is, as used in error messages.
+	pub fn type_name(&self) -> &'static str {
+		match self {
+			Expression::Atom(Atom::Number(Numeric::Int(_))) => "Integer",
+			Expression::Atom(Atom::Number(Numeric::Float(_))) => "Float",
+			Expression::Atom(Atom::String(_)) => "String",
+			Expression::Atom(Atom::Symbol(_)) => "Symbol",
+			Expression::Atom(Atom::Keyword(_)) => "Keyword",
+			Expression::Atom(Atom::Bool(_)) => "Bool",
+			Expression::Atom(Atom::Value(Value::Variable(expression))) => expression.type_name(),
+			Expression::Atom(Atom::Value(Value::UserDef(_))) => "Function",
+			Expression::Atom(Atom::Value(_)) => "Builtin",
+			Expression::SExpression(_) => "SExpression",
+			Expression::QExpression(_) => "QExpression",
+			Expression::Null => "Null",
+		}
+	}
+
 	pub fn get_from_q_expression(self) -> Result<VecDeque<Expression>, LockjawRuntimeError> {
 		match self {
 			Expression::QExpression(val) => Ok(val),